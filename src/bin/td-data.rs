@@ -0,0 +1,265 @@
+//! Проверка игровых данных без запуска игры — для pre-commit.
+//!
+//! ```text
+//! cargo run --bin td-data [-- --assets path/to/assets]
+//! ```
+//!
+//! Разбирает `assets/data/*` (`.json`, `.ron`, `.toml`) теми же типами, что и игра, вместе с паками из
+//! `assets/mods`, проверяет ссылки между файлами (снаряды башен, враги в волнах, башни и
+//! враги в реакциях на стрим, модели на диске), уровни из `assets/data/levels`, таблицы
+//! переводов из `assets/locale` и завершается с кодом 1, если нашлась хоть одна ошибка. Конфликты паков и пробелы
+//! в переводах — предупреждения.
+
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use stream_td::cli::arg_value;
+use stream_td::data_format::{parse_data, resolve_data_file};
+use stream_td::data_load::{
+    ENEMIES_JSON, EnemiesJsonFile, PROJECTILES_JSON, ProjectilesJsonFile, TOWERS_JSON, TowersJsonFile, assets_dir,
+    merge_enemy_files, merge_projectile_files, merge_tower_files, parse_enemies, parse_projectiles, parse_towers,
+};
+use stream_td::game_action::GameAction;
+use stream_td::level::{DEFAULT_LEVEL, LEVELS_DIR, LevelDef, Levels, TerrainDef};
+use stream_td::localization::{FALLBACK_LANGUAGE, LOCALE_DIR, check_tables, load_tables};
+use stream_td::moderation::ModerationConfig;
+use stream_td::mods::ModPacks;
+use stream_td::stream_event::StreamReactionsFile;
+use stream_td::target::{EnemyDef, TargetCatalog};
+use stream_td::wave::{WAVES_JSON, WaveSchedule};
+
+const STREAM_REACTIONS: &str = "data/stream_reactions.json";
+const MODERATION: &str = "data/moderation.json";
+
+#[derive(Default)]
+struct Report {
+    errors: Vec<String>,
+}
+
+impl Report {
+    fn ok(&self, file: &str, summary: String) {
+        println!("ok    {}: {}", file, summary);
+    }
+
+    fn warn(&self, w: impl std::fmt::Display) {
+        println!("warn  {}", w);
+    }
+
+    fn error(&mut self, e: impl std::fmt::Display) {
+        let e = e.to_string();
+        println!("error {}", e);
+        self.errors.push(e);
+    }
+}
+
+/// Файл `file` из `assets` в любом из форматов данных.
+fn read_data<T: serde::de::DeserializeOwned>(assets: &Path, file: &str) -> anyhow::Result<T> {
+    let file = resolve_data_file(assets, file).unwrap_or_else(|| file.to_string());
+    let path = assets.join(&file);
+    let text = std::fs::read_to_string(&path).with_context(|| format!("{}: cannot read {}", file, path.display()))?;
+    parse_data(Path::new(&file), &text).with_context(|| file.clone())
+}
+
+/// Базовый файл и его версии из паков; нечитаемые слои пропускаются с ошибкой.
+fn read_layers<T: serde::de::DeserializeOwned>(
+    assets: &Path,
+    packs: &ModPacks,
+    file: &str,
+    report: &mut Report,
+) -> Vec<(String, T)> {
+    let mut layers = Vec::new();
+    for path in packs.layers(assets, file) {
+        match read_data(assets, &path) {
+            Ok(layer) => layers.push((path, layer)),
+            Err(e) => report.error(format!("{:#}", e)),
+        }
+    }
+    layers
+}
+
+fn check_mods(packs: &ModPacks, report: &mut Report) {
+    for e in &packs.errors {
+        report.error(e);
+    }
+    let ids: Vec<_> = packs.packs.iter().map(|p| p.manifest.id.as_str()).collect();
+    report.ok("mods", format!("{} packs in load order: [{}]", ids.len(), ids.join(", ")));
+}
+
+fn check_projectiles(assets: &Path, packs: &ModPacks, report: &mut Report) -> Vec<String> {
+    let layers: Vec<(String, ProjectilesJsonFile)> = read_layers(assets, packs, PROJECTILES_JSON, report);
+    let layers: Vec<_> = layers.iter().map(|(path, f)| (path.as_str(), f)).collect();
+    let (file, conflicts) = merge_projectile_files(&layers);
+    for c in conflicts {
+        report.warn(c);
+    }
+    let (projectiles, errors) = parse_projectiles(&file, assets);
+    for e in errors {
+        report.error(format!("{:#}", e));
+    }
+    report.ok(PROJECTILES_JSON, format!("{} valid projectiles", projectiles.len()));
+    projectiles.into_iter().map(|p| p.id).collect()
+}
+
+fn check_towers(assets: &Path, packs: &ModPacks, projectiles: &[String], report: &mut Report) -> Vec<String> {
+    let layers: Vec<(String, TowersJsonFile)> = read_layers(assets, packs, TOWERS_JSON, report);
+    let layers: Vec<_> = layers.iter().map(|(path, f)| (path.as_str(), f)).collect();
+    let (file, conflicts) = merge_tower_files(&layers);
+    for c in conflicts {
+        report.warn(c);
+    }
+    let (towers, errors) = parse_towers(&file, projectiles, assets);
+    for e in errors {
+        report.error(format!("{:#}", e));
+    }
+    for t in towers.iter().filter(|t| !t.name.contains_key(FALLBACK_LANGUAGE)) {
+        report.warn(format!("{}: tower '{}': field `name` has no '{}' text", TOWERS_JSON, t.id, FALLBACK_LANGUAGE));
+    }
+    report.ok(TOWERS_JSON, format!("{} valid towers", towers.len()));
+    towers.into_iter().map(|t| t.id).collect()
+}
+
+/// Каталог врагов для проверки ссылок на них; модели и анимации не нужны.
+fn check_enemies(assets: &Path, packs: &ModPacks, report: &mut Report) -> TargetCatalog {
+    let layers: Vec<(String, EnemiesJsonFile)> = read_layers(assets, packs, ENEMIES_JSON, report);
+    let layers: Vec<_> = layers.iter().map(|(path, f)| (path.as_str(), f)).collect();
+    let (file, conflicts) = merge_enemy_files(&layers);
+    for c in conflicts {
+        report.warn(c);
+    }
+    let (enemies, errors) = parse_enemies(&file, assets);
+    for e in errors {
+        report.error(format!("{:#}", e));
+    }
+    for e in enemies.iter().filter(|e| !e.name.contains_key(FALLBACK_LANGUAGE)) {
+        report.warn(format!("{}: enemy '{}': field `name` has no '{}' text", ENEMIES_JSON, e.id, FALLBACK_LANGUAGE));
+    }
+    report.ok(ENEMIES_JSON, format!("{} valid enemies", enemies.len()));
+    TargetCatalog::new(enemies.into_iter().map(|j| EnemyDef { id: j.id, ..EnemyDef::default() }).collect())
+}
+
+/// Ссылки из действия на башни и врагов.
+fn check_action(action: &GameAction, towers: &[String], catalog: &TargetCatalog) -> Result<(), String> {
+    let tower = match action {
+        GameAction::PlaceTower { tower, .. } => Some(tower),
+        GameAction::SponsorTower { tower } => tower.as_ref(),
+        GameAction::SpawnEnemy { kind: Some(kind), .. } if catalog.find(kind).is_none() => {
+            return Err(format!("unknown enemy '{}'", kind));
+        }
+        _ => None,
+    };
+    match tower {
+        Some(tower) if !towers.contains(tower) => Err(format!("unknown tower '{}'", tower)),
+        _ => Ok(()),
+    }
+}
+
+fn check_stream_reactions(assets: &Path, towers: &[String], catalog: &TargetCatalog, report: &mut Report) {
+    let file: StreamReactionsFile = match read_data(assets, STREAM_REACTIONS) {
+        Ok(file) => file,
+        Err(e) => return report.error(format!("{:#}", e)),
+    };
+    for (i, reaction) in file.reactions.iter().enumerate() {
+        if let Err(e) = check_action(&reaction.action, towers, catalog) {
+            report.error(format!("{}: reaction #{} ({:?}): field `action`: {}", STREAM_REACTIONS, i, reaction.on, e));
+        }
+    }
+    report.ok(STREAM_REACTIONS, format!("{} reactions", file.reactions.len()));
+}
+
+/// Проверяются все версии расписания, хотя игра возьмёт последнюю.
+fn check_waves(assets: &Path, packs: &ModPacks, catalog: &TargetCatalog, report: &mut Report) {
+    for (path, schedule) in read_layers::<WaveSchedule>(assets, packs, WAVES_JSON, report) {
+        for e in schedule.validate(&path, catalog) {
+            report.error(e);
+        }
+        report.ok(&path, format!("{} waves", schedule.waves.len()));
+    }
+}
+
+/// Уровни целиком: поля, id стартовых башен, модель земли и файл волн.
+fn check_levels(assets: &Path, packs: &ModPacks, towers: &[String], catalog: &TargetCatalog, report: &mut Report) {
+    let levels = Levels::discover(assets, Some(packs));
+    if !levels.files.iter().any(|f| f.id == DEFAULT_LEVEL) {
+        report.error(format!("{}: default level '{}' is missing", LEVELS_DIR, DEFAULT_LEVEL));
+    }
+    for file in &levels.files {
+        let level: LevelDef = match read_data(assets, &file.path) {
+            Ok(level) => level,
+            Err(e) => {
+                report.error(format!("{:#}", e));
+                continue;
+            }
+        };
+        for e in level.validate(&file.path) {
+            report.error(e);
+        }
+        for t in level.towers.iter().filter(|t| !towers.contains(&t.id)) {
+            report.error(format!("{}: field `towers`: unknown tower '{}'", file.path, t.id));
+        }
+        if let TerrainDef::Scene(glb) = &level.terrain
+            && !assets.join(glb).is_file()
+        {
+            report.error(format!("{}: field `terrain`: file assets/{} does not exist", file.path, glb));
+        }
+        match resolve_data_file(assets, &level.waves) {
+            None => report.error(format!("{}: field `waves`: file assets/{} does not exist", file.path, level.waves)),
+            // Общее расписание уже проверено в `check_waves`.
+            Some(waves) if waves == WAVES_JSON => {}
+            Some(waves) => match read_data::<WaveSchedule>(assets, &waves) {
+                Ok(schedule) => {
+                    for e in schedule.validate(&waves, catalog) {
+                        report.error(e);
+                    }
+                }
+                Err(e) => report.error(format!("{:#}", e)),
+            },
+        }
+        report.ok(&file.path, format!("{} paths, {} starting towers", level.paths.len(), level.towers.len()));
+    }
+}
+
+fn check_locales(assets: &Path, packs: &ModPacks, report: &mut Report) {
+    let (tables, errors) = load_tables(assets, Some(packs));
+    for e in errors {
+        report.error(e);
+    }
+    if !tables.contains_key(FALLBACK_LANGUAGE) {
+        return report.error(format!("{}: no table for the fallback language '{}'", LOCALE_DIR, FALLBACK_LANGUAGE));
+    }
+    for problem in check_tables(&tables) {
+        report.warn(problem);
+    }
+    let languages: Vec<_> = tables.keys().cloned().collect();
+    report.ok(LOCALE_DIR, format!("languages: [{}]", languages.join(", ")));
+}
+
+fn check_moderation(assets: &Path, report: &mut Report) {
+    match read_data::<ModerationConfig>(assets, MODERATION) {
+        Ok(config) => report.ok(MODERATION, format!("{} banned viewers", config.banned.len())),
+        Err(e) => report.error(format!("{:#}", e)),
+    }
+}
+
+fn main() -> ExitCode {
+    let assets = arg_value("--assets").map(PathBuf::from).unwrap_or_else(assets_dir);
+    let packs = ModPacks::discover(&assets);
+    let mut report = Report::default();
+
+    check_mods(&packs, &mut report);
+    let projectiles = check_projectiles(&assets, &packs, &mut report);
+    let towers = check_towers(&assets, &packs, &projectiles, &mut report);
+    let catalog = check_enemies(&assets, &packs, &mut report);
+    check_waves(&assets, &packs, &catalog, &mut report);
+    check_stream_reactions(&assets, &towers, &catalog, &mut report);
+    check_levels(&assets, &packs, &towers, &catalog, &mut report);
+    check_moderation(&assets, &mut report);
+    check_locales(&assets, &packs, &mut report);
+
+    if report.errors.is_empty() {
+        println!("all data is valid");
+        ExitCode::SUCCESS
+    } else {
+        println!("{} error(s)", report.errors.len());
+        ExitCode::FAILURE
+    }
+}
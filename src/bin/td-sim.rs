@@ -0,0 +1,231 @@
+//! Балансный прогон: расстановка башен против расписания волн, без окна и на
+//! максимальной скорости. Та же симуляция, что и в игре, — наведение башен и снаряды
+//! из `tower.rs`/`projectile.rs`.
+//!
+//! ```text
+//! cargo run --release --bin td-sim -- --layout layout.json [--level canyon] [--waves waves.json]
+//!     [--wave-count 8] [--seed 42] [--max-wave-seconds 300] [--format csv|json] [--out report.csv]
+//! ```
+//!
+//! `layout.json` (или `.ron`, `.toml`): `{"towers": [{"id": "basic", "pos": [0.0, 0.0, 0.0]}]}`; башни
+//! должны стоять в зонах строительства уровня. Уровень — из `assets/data/levels`, по умолчанию
+//! `meadow`; без `--waves` берётся файл волн уровня. Отчёт — по волнам (утечки, убийства, оверкилл)
+//! и по башням (убийства). Фоновых целей нет — только волны.
+
+use anyhow::{Context, bail};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::process::ExitCode;
+use stream_td::cli::arg_value;
+use stream_td::data_format::parse_data;
+use stream_td::fixed_step::{SIMULATION_HZ, SimulationTick};
+use stream_td::headless::SimHarness;
+use stream_td::level::ActiveLevel;
+use stream_td::rng::GameRng;
+use stream_td::target::{SpawnTargetEvent, TargetCatalog, TargetKilled, TargetLeaked};
+use stream_td::tower::{Tower, TowerId, TowerStats};
+use stream_td::wave::{WaveSchedule, WaveState};
+
+const DEFAULT_WAVE_SECONDS: u32 = 300;
+/// Сколько волн гонять, если в расписании их нет.
+const DEFAULT_WAVE_COUNT: u32 = 10;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Layout {
+    towers: Vec<LayoutTower>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LayoutTower {
+    id: String,
+    pos: [f32; 3],
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+struct WaveReport {
+    wave: u32,
+    spawned: u32,
+    kills: u32,
+    leaks: u32,
+    overkill: f32,
+    seconds: f32,
+    /// Волна не закончилась за отведённое время.
+    timed_out: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct TowerReport {
+    id: u32,
+    tower: String,
+    pos: [f32; 3],
+    level: u32,
+    kills: u32,
+}
+
+#[derive(Debug, Serialize)]
+struct BalanceReport {
+    seed: u64,
+    level: String,
+    waves: Vec<WaveReport>,
+    towers: Vec<TowerReport>,
+}
+
+/// Счётчики по номеру волны, собираются из событий симуляции.
+#[derive(Resource, Default)]
+struct WaveTally(BTreeMap<u32, WaveReport>);
+
+fn tally_waves(
+    mut tally: ResMut<WaveTally>,
+    mut spawned: EventReader<SpawnTargetEvent>,
+    mut killed: EventReader<TargetKilled>,
+    mut leaked: EventReader<TargetLeaked>,
+) {
+    for wave in spawned.read().filter_map(|e| e.wave) {
+        tally.0.entry(wave).or_default().spawned += 1;
+    }
+    for e in killed.read() {
+        let Some(wave) = e.wave else { continue; };
+        let report = tally.0.entry(wave).or_default();
+        report.kills += 1;
+        report.overkill += e.overkill;
+    }
+    for wave in leaked.read().filter_map(|e| e.wave) {
+        tally.0.entry(wave).or_default().leaks += 1;
+    }
+}
+
+/// Раскладка и волны — в любом из форматов данных, по расширению.
+fn read_data<T: serde::de::DeserializeOwned>(path: &Path) -> anyhow::Result<T> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+    parse_data(path, &text).with_context(|| path.display().to_string())
+}
+
+fn parse_arg<T: std::str::FromStr>(flag: &str) -> anyhow::Result<Option<T>> {
+    match arg_value(flag) {
+        Some(v) => v.parse().map(Some).map_err(|_| anyhow::anyhow!("{}: bad value '{}'", flag, v)),
+        None => Ok(None),
+    }
+}
+
+fn simulate() -> anyhow::Result<BalanceReport> {
+    let layout_path = arg_value("--layout").context("--layout <file> is required")?;
+    let layout: Layout = read_data(Path::new(&layout_path))?;
+    let seed = parse_arg("--seed")?.unwrap_or(0);
+    let wave_seconds: u32 = parse_arg("--max-wave-seconds")?.unwrap_or(DEFAULT_WAVE_SECONDS);
+
+    let mut sim = SimHarness::with_seed(seed);
+    // Фоновые цели отвлекали бы башни и путали счёт по волнам.
+    sim.pause_ambient_spawn();
+    if let Some(path) = arg_value("--waves") {
+        let schedule: WaveSchedule = read_data(Path::new(&path))?;
+        let errors = schedule.validate(&path, sim.app.world().resource::<TargetCatalog>());
+        if !errors.is_empty() {
+            bail!("{}", errors.join("\n"));
+        }
+        sim.app.insert_resource(schedule);
+    }
+    let schedule = sim.app.world().resource::<WaveSchedule>().clone();
+    let wave_count = match parse_arg("--wave-count")? {
+        Some(n) => n,
+        None if schedule.waves.is_empty() => DEFAULT_WAVE_COUNT,
+        None => schedule.waves.len() as u32,
+    };
+
+    let level = sim.app.world().resource::<ActiveLevel>().clone();
+    if let Some(t) = layout.towers.iter().find(|t| !level.can_build(Vec3::from_array(t.pos))) {
+        bail!("{}: tower '{}' at {:?} is outside the build zones of level '{}'", layout_path, t.id, t.pos, level.id);
+    }
+
+    sim.clear_towers();
+    for t in &layout.towers {
+        sim.place_tower(&t.id, Vec3::from_array(t.pos));
+    }
+    sim.step(1);
+    let placed = sim.app.world_mut().query::<&Tower>().iter(sim.app.world()).count();
+    if placed != layout.towers.len() {
+        bail!("{}: only {} of {} towers were placed, check tower ids in the layout", layout_path, placed, layout.towers.len());
+    }
+
+    sim.app.init_resource::<WaveTally>().add_systems(Last, tally_waves);
+    let max_frames = wave_seconds * SIMULATION_HZ as u32;
+    let mut finished = Vec::new();
+    for wave in 1..=wave_count {
+        let started = sim.app.world().resource::<SimulationTick>().0;
+        sim.start_wave();
+        let ended = sim.step_until(max_frames, |world| world.resource::<WaveState>().ended >= wave);
+        let world = sim.app.world();
+        let mut report = world.resource::<WaveTally>().0.get(&wave).cloned().unwrap_or_default();
+        report.wave = wave;
+        report.seconds = ((world.resource::<SimulationTick>().0 - started) as f64 / SIMULATION_HZ) as f32;
+        report.timed_out = !ended;
+        if !ended {
+            eprintln!("wave {} did not end in {}s", wave, wave_seconds);
+        }
+        finished.push(report);
+    }
+
+    let world = sim.app.world_mut();
+    let mut towers: Vec<_> = world
+        .query::<(&TowerId, &Tower, &TowerStats, &Transform)>()
+        .iter(world)
+        .map(|(id, tower, stats, transform)| TowerReport {
+            id: id.0,
+            tower: stats.def_id.clone(),
+            pos: transform.translation.to_array(),
+            level: tower.level,
+            kills: tower.kills,
+        })
+        .collect();
+    towers.sort_by_key(|t| t.id);
+
+    Ok(BalanceReport { seed: world.resource::<GameRng>().seed(), level: level.id, waves: finished, towers })
+}
+
+fn to_csv(report: &BalanceReport) -> String {
+    let mut out = String::from("wave,spawned,kills,leaks,overkill,seconds,timed_out\n");
+    for w in &report.waves {
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{:.1},{:.1},{}",
+            w.wave, w.spawned, w.kills, w.leaks, w.overkill, w.seconds, w.timed_out
+        );
+    }
+    out.push_str("\ntower_id,tower,x,y,z,level,kills\n");
+    for t in &report.towers {
+        let _ = writeln!(out, "{},{},{},{},{},{},{}", t.id, t.tower, t.pos[0], t.pos[1], t.pos[2], t.level, t.kills);
+    }
+    out
+}
+
+fn main() -> ExitCode {
+    let report = match simulate() {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let text = match arg_value("--format").as_deref() {
+        None | Some("csv") => to_csv(&report),
+        Some("json") => serde_json::to_string_pretty(&report).expect("report is serializable"),
+        Some(other) => {
+            eprintln!("error: unknown --format '{}', expected csv or json", other);
+            return ExitCode::FAILURE;
+        }
+    };
+    match arg_value("--out") {
+        Some(path) => {
+            if let Err(e) = std::fs::write(&path, text) {
+                eprintln!("error: cannot write {}: {}", path, e);
+                return ExitCode::FAILURE;
+            }
+        }
+        None => print!("{}", text),
+    }
+    ExitCode::SUCCESS
+}
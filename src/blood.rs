@@ -4,6 +4,7 @@ use bevy::math::primitives::Rectangle;
 use bevy::color::Srgba;
 use rand::Rng;
 use crate::AppState;
+use crate::rng::GameRng;
 
 #[derive(Event)]
 pub struct SpawnBlood {
@@ -24,10 +25,11 @@ fn spawn_blood_splash(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     assets: Res<AssetServer>,
+    mut rng: ResMut<GameRng>,
 ) {
     for SpawnBlood { pos } in ev.read() {
 
-        let mut rng = rand::rng();
+        let rng = &mut rng.cosmetic;
         let scale = rng.random_range(1.0..1.6);
         let y_offset = 0.01;
 
@@ -11,7 +11,8 @@ impl bevy::prelude::Plugin for CustomCameraPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, spawn_camera)
             .add_systems(Update, (camera_controls, camera_zoom, camera_rotate)
-                .run_if(not(in_state(AppState::Menu))));
+                .run_if(not(in_state(AppState::Menu)))
+                .run_if(not(in_state(AppState::Loading))));
     }
 }
 
@@ -0,0 +1,299 @@
+use crate::AppState;
+use crate::cli::arg_value;
+use crate::game_action::{GameAction, GameActions};
+use crate::localization::Locale;
+use crate::moderation::{CommandRejected, Moderation, RejectReason};
+use crate::stream_event::StreamEventKind;
+use crate::tower::TowerId;
+use crate::wave::WaveState;
+use bevy::prelude::*;
+use bevy_inspector_egui::{bevy_egui, egui};
+use std::collections::VecDeque;
+
+/// Очередь команд зрителей с поправкой на задержку стрима.
+///
+/// Зритель видит игру с опозданием на `--stream-delay` секунд, поэтому по времени получения
+/// команды восстанавливается волна, которую он тогда видел. Если с тех пор волна уже
+/// сменилась, `!wave` отклоняется, а не запускает ещё одну; так же отклоняются улучшение и
+/// продажа башни, которой уже нет. Пока игра на паузе, команды ждут; старше
+/// `--command-max-age` секунд — выбрасываются. Выключатель эффектов чата (`F8`) отклоняет
+/// и всё, что уже стоит в очереди.
+pub struct CommandQueuePlugin;
+
+/// `F9` показывает очередь команд.
+pub struct CommandQueuePanelPlugin;
+
+impl Plugin for CommandQueuePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CommandQueueConfig>()
+            .init_resource::<CommandQueue>()
+            .add_systems(PostUpdate, (record_wave_history, run_command_queue).chain());
+    }
+}
+
+impl Plugin for CommandQueuePanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CommandQueuePanelVisible>()
+            .add_systems(Update, toggle_command_queue_panel)
+            .add_systems(
+                bevy_egui::EguiPrimaryContextPass,
+                ui_command_queue
+                    .run_if(resource_exists::<crate::egui_setup::EguiConfigured>)
+                    .run_if(|visible: Res<CommandQueuePanelVisible>| visible.0),
+            );
+    }
+}
+
+const HISTORY_SECONDS: f64 = 120.0;
+const RECENT_OUTCOMES: usize = 20;
+
+#[derive(Resource, Clone)]
+pub struct CommandQueueConfig {
+    /// На сколько секунд зритель отстаёт от игры.
+    pub stream_delay: f64,
+    /// Команды старше этого (в секундах с момента получения) не выполняются.
+    pub max_age: f64,
+}
+
+impl Default for CommandQueueConfig {
+    fn default() -> Self {
+        let secs = |flag: &str, default: f64| arg_value(flag).and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self {
+            stream_delay: secs("--stream-delay", 0.0),
+            max_age: secs("--command-max-age", 30.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct QueuedCommand {
+    pub user: String,
+    pub kind: StreamEventKind,
+    pub text: Option<String>,
+    pub action: GameAction,
+    /// Реальное время получения, секунды.
+    pub received_at: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct CommandOutcome {
+    pub user: String,
+    pub action: GameAction,
+    pub result: Result<(), String>,
+}
+
+#[derive(Resource, Default)]
+pub struct CommandQueue {
+    pending: VecDeque<QueuedCommand>,
+    recent: VecDeque<CommandOutcome>,
+    /// (время, номер волны) при каждой смене волны.
+    wave_history: VecDeque<(f64, u32)>,
+    /// Последняя волна, которую запросила сама очередь, — `WaveState` обновится позже в кадре.
+    requested_wave: u32,
+}
+
+impl CommandQueue {
+    /// Ставит команду в очередь; `now` — секунды реального времени.
+    pub fn push(&mut self, user: &str, kind: StreamEventKind, text: Option<String>, action: GameAction, now: f64) {
+        self.pending.push_back(QueuedCommand { user: user.to_string(), kind, text, action, received_at: now });
+    }
+
+    /// Волна, которую видел зритель, когда писал команду.
+    pub fn seen_wave(&self, cmd: &QueuedCommand, config: &CommandQueueConfig) -> u32 {
+        self.wave_at(cmd.received_at - config.stream_delay)
+    }
+
+    /// Номер волны в момент `t`.
+    pub fn wave_at(&self, t: f64) -> u32 {
+        self.wave_history
+            .iter()
+            .rev()
+            .find(|(at, _)| *at <= t)
+            .or(self.wave_history.front())
+            .map_or(0, |(_, wave)| *wave)
+    }
+
+    pub fn pending(&self) -> impl Iterator<Item = &QueuedCommand> {
+        self.pending.iter()
+    }
+
+    pub fn recent(&self) -> impl DoubleEndedIterator<Item = &CommandOutcome> {
+        self.recent.iter()
+    }
+
+    fn remember(&mut self, cmd: &QueuedCommand, result: Result<(), String>) {
+        if self.recent.len() >= RECENT_OUTCOMES {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(CommandOutcome { user: cmd.user.clone(), action: cmd.action.clone(), result });
+    }
+}
+
+fn record_wave_history(mut queue: ResMut<CommandQueue>, wave: Res<WaveState>, time: Res<Time<Real>>) {
+    let now = time.elapsed_secs_f64();
+    if queue.wave_history.back().is_none_or(|(_, w)| *w != wave.index) {
+        queue.wave_history.push_back((now, wave.index));
+    }
+    // Самую старую запись храним всегда: она описывает всё, что было до неё.
+    while queue.wave_history.len() > 1 && queue.wave_history[1].0 < now - HISTORY_SECONDS {
+        queue.wave_history.pop_front();
+    }
+    // Запрошенная волна либо уже началась, либо начнётся в этом кадре.
+    queue.requested_wave = queue.requested_wave.clamp(wave.index, wave.index + 1);
+}
+
+/// Отклоняет команду, если то, на что смотрел зритель, уже в прошлом: волна сменилась
+/// или башню успели продать.
+fn check_stale(
+    cmd: &QueuedCommand,
+    seen_wave: u32,
+    current_wave: u32,
+    towers: &Query<&TowerId>,
+) -> Result<(), RejectReason> {
+    match cmd.action {
+        GameAction::StartWave { wave } if current_wave >= wave.unwrap_or(seen_wave + 1) => {
+            Err(RejectReason::StaleWave { seen: seen_wave, current: current_wave })
+        }
+        GameAction::UpgradeTower { tower } | GameAction::SellTower { tower }
+            if !towers.iter().any(|id| id.0 == tower) =>
+        {
+            Err(RejectReason::StaleTower { tower })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Отклонённая команда попадает и в журнал модерации, и в «недавние» панели очереди.
+fn reject(queue: &mut CommandQueue, rejected: &mut EventWriter<CommandRejected>, cmd: &QueuedCommand, reason: RejectReason) {
+    rejected.write(CommandRejected { user: cmd.user.clone(), kind: cmd.kind, text: cmd.text.clone(), reason });
+    queue.remember(cmd, Err(format!("{:?}", reason)));
+}
+
+fn run_command_queue(
+    mut queue: ResMut<CommandQueue>,
+    (config, moderation): (Res<CommandQueueConfig>, Res<Moderation>),
+    state: Res<State<AppState>>,
+    time: Res<Time<Real>>,
+    mut rejected: EventWriter<CommandRejected>,
+    mut actions: GameActions,
+    towers: Query<&TowerId>,
+) {
+    let now = time.elapsed_secs_f64();
+    let in_game = *state.get() == AppState::InGame;
+
+    if !moderation.enabled {
+        while let Some(cmd) = queue.pending.pop_front() {
+            reject(&mut queue, &mut rejected, &cmd, RejectReason::ChatEffectsDisabled);
+        }
+        return;
+    }
+
+    let mut held = VecDeque::new();
+    while let Some(cmd) = queue.pending.pop_front() {
+        let age = now - cmd.received_at;
+        let seen_wave = queue.seen_wave(&cmd, &config);
+        let checked = if age > config.max_age {
+            Err(RejectReason::Expired)
+        } else {
+            check_stale(&cmd, seen_wave, queue.requested_wave, &towers)
+        };
+        if let Err(reason) = checked {
+            reject(&mut queue, &mut rejected, &cmd, reason);
+            continue;
+        }
+        if !in_game {
+            held.push_back(cmd);
+            continue;
+        }
+
+        let action = match cmd.action {
+            GameAction::StartWave { wave: None } => GameAction::StartWave { wave: Some(seen_wave + 1) },
+            ref action => action.clone(),
+        };
+        let result = actions.apply_as(&action, Some(&cmd.user));
+        match &result {
+            Ok(()) => {
+                info!("{} ({:?}, {:.1}s old) -> {:?}", cmd.user, cmd.kind, age, action);
+                if let GameAction::StartWave { wave: Some(wave) } = action {
+                    queue.requested_wave = wave;
+                }
+            }
+            Err(e) => warn!("Command {:?} from {} failed: {}", action, cmd.user, e),
+        }
+        queue.remember(&cmd, result);
+    }
+    queue.pending = held;
+}
+
+#[derive(Resource, Default)]
+struct CommandQueuePanelVisible(bool);
+
+fn toggle_command_queue_panel(keyboard: Res<ButtonInput<KeyCode>>, mut visible: ResMut<CommandQueuePanelVisible>) {
+    if keyboard.just_pressed(KeyCode::F9) {
+        visible.0 = !visible.0;
+    }
+}
+
+fn ui_command_queue(
+    mut egui_ctx: bevy_egui::EguiContexts,
+    queue: Res<CommandQueue>,
+    config: Res<CommandQueueConfig>,
+    wave: Res<WaveState>,
+    time: Res<Time<Real>>,
+    locale: Res<Locale>,
+) {
+    let Ok(ctx) = egui_ctx.ctx_mut() else { return; };
+    let now = time.elapsed_secs_f64();
+
+    egui::Area::new(egui::Id::new("command_queue_area"))
+        .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -12.0])
+        .show(ctx, |ui| {
+            let frame = egui::Frame::window(&ctx.style())
+                .fill(ui.visuals().panel_fill)
+                .corner_radius(egui::CornerRadius::same(6))
+                .inner_margin(egui::Margin::symmetric(10, 8))
+                .stroke(ui.visuals().widgets.noninteractive.bg_stroke);
+
+            egui::Frame::show(frame, ui, |ui| {
+                ui.heading(locale.t("command_queue.title"));
+                ui.label(locale.fmt(
+                    "command_queue.summary",
+                    &[
+                        ("delay", &format!("{:.1}", config.stream_delay)),
+                        ("max_age", &format!("{:.0}", config.max_age)),
+                        ("wave", &wave.index),
+                        ("seen", &queue.wave_at(now - config.stream_delay)),
+                    ],
+                ));
+                ui.separator();
+                if queue.pending.is_empty() {
+                    ui.label(locale.t("command_queue.empty"));
+                } else {
+                    egui::Grid::new("command_queue_grid").striped(true).show(ui, |ui| {
+                        for key in
+                            ["command_queue.viewer", "command_queue.action", "command_queue.age", "command_queue.seen_wave"]
+                        {
+                            ui.label(locale.t(key));
+                        }
+                        ui.end_row();
+                        for cmd in queue.pending() {
+                            ui.label(&cmd.user);
+                            ui.label(format!("{:?}", cmd.action));
+                            ui.label(format!("{:.1}s", now - cmd.received_at));
+                            ui.label(format!("{}", queue.seen_wave(cmd, &config)));
+                            ui.end_row();
+                        }
+                    });
+                }
+                ui.separator();
+                ui.label(locale.t("command_queue.recent"));
+                for outcome in queue.recent().rev() {
+                    let text = format!("{}: {:?}", outcome.user, outcome.action);
+                    match &outcome.result {
+                        Ok(()) => ui.label(text),
+                        Err(e) => ui.colored_label(egui::Color32::from_rgb(230, 80, 60), format!("{} — {}", text, e)),
+                    };
+                }
+            });
+        });
+}
@@ -0,0 +1,318 @@
+use crate::cli::arg_value;
+use crate::game_action::{GameAction, GameActions};
+use crate::stream_event::{ChannelSource, StreamEvent, StreamSources};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+
+/// Локальный TCP-сервер команд: одна JSON-команда на строку, один JSON-ответ на строку.
+///
+/// ```text
+/// {"id": 1, "cmd": "spawn_enemy", "kind": "Target_2", "count": 3}
+/// {"id": 2, "cmd": "grant_gold", "amount": 50}
+/// {"id": 3, "cmd": "start_wave"}
+/// {"id": 4, "cmd": "place_tower", "tower": "basic", "pos": [1.0, 0.0, 2.0]}
+/// {"id": 5, "cmd": "stream_event", "event": {"type": "raid", "user": "someone", "viewers": 40}}
+/// ```
+///
+/// Включается флагом `--command-bind 127.0.0.1:7878`; одновременно держит не больше
+/// [`MAX_CONNECTIONS`] клиентов, лишним отвечает ошибкой и закрывает соединение.
+pub struct CommandServerPlugin;
+
+impl Plugin for CommandServerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CommandServerConfig>()
+            .add_systems(Startup, start_command_server)
+            .add_systems(
+                Update,
+                handle_external_commands.run_if(resource_exists::<CommandInbox>),
+            );
+    }
+}
+
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+pub const MAX_CONNECTIONS: usize = 4;
+
+#[derive(Resource, Clone)]
+pub struct CommandServerConfig {
+    pub bind: Option<String>,
+}
+
+impl Default for CommandServerConfig {
+    fn default() -> Self {
+        Self { bind: arg_value("--command-bind") }
+    }
+}
+
+/// Строка протокола: либо [`GameAction`], либо `{"cmd": "stream_event", "event": {...}}`
+/// для подачи события стрима (подписка, рейд…) в общий конвейер.
+#[derive(Debug)]
+enum ExternalCommand {
+    Action(GameAction),
+    StreamEvent(StreamEvent),
+}
+
+#[derive(Deserialize)]
+struct StreamEventCommand {
+    event: StreamEvent,
+}
+
+fn parse_command_line(line: &str) -> (Option<serde_json::Value>, Result<ExternalCommand, String>) {
+    let value: serde_json::Value = match serde_json::from_str(line) {
+        Ok(v) => v,
+        Err(e) => return (None, Err(format!("invalid json: {}", e))),
+    };
+    let id = value.get("id").cloned();
+    let command = if value.get("cmd").and_then(|c| c.as_str()) == Some("stream_event") {
+        serde_json::from_value::<StreamEventCommand>(value).map(|c| ExternalCommand::StreamEvent(c.event))
+    } else {
+        serde_json::from_value::<GameAction>(value).map(ExternalCommand::Action)
+    };
+    (id, command.map_err(|e| format!("invalid command: {}", e)))
+}
+
+#[derive(Debug, Serialize)]
+struct CommandReply {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<serde_json::Value>,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl CommandReply {
+    fn ok(id: Option<serde_json::Value>) -> Self {
+        Self { id, ok: true, error: None }
+    }
+
+    fn error(id: Option<serde_json::Value>, error: impl Into<String>) -> Self {
+        Self { id, ok: false, error: Some(error.into()) }
+    }
+}
+
+struct PendingCommand {
+    action: GameAction,
+    reply: Sender<Result<(), String>>,
+}
+
+#[derive(Resource)]
+struct CommandInbox(Mutex<Receiver<PendingCommand>>);
+
+fn start_command_server(
+    mut commands: Commands,
+    config: Res<CommandServerConfig>,
+    mut sources: ResMut<StreamSources>,
+) {
+    let Some(bind) = config.bind.clone() else { return; };
+    let listener = match TcpListener::bind(&bind) {
+        Ok(l) => l,
+        Err(e) => {
+            error!("Command server: cannot bind {}: {}", bind, e);
+            return;
+        }
+    };
+    info!("Command server listening on {}", bind);
+
+    let (tx, rx) = mpsc::channel();
+    commands.insert_resource(CommandInbox(Mutex::new(rx)));
+    let (stream_tx, source) = ChannelSource::new(format!("tcp {}", bind));
+    sources.add(source);
+
+    let connections = Arc::new(AtomicUsize::new(0));
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(mut stream) => {
+                    if connections.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                        connections.fetch_sub(1, Ordering::SeqCst);
+                        warn!("Command server: too many clients, dropping {:?}", stream.peer_addr());
+                        if let Ok(mut json) = serde_json::to_string(&CommandReply::error(None, "too many clients")) {
+                            json.push('\n');
+                            let _ = stream.write_all(json.as_bytes());
+                        }
+                        continue;
+                    }
+                    let tx = tx.clone();
+                    let stream_tx = stream_tx.clone();
+                    let connections = connections.clone();
+                    std::thread::spawn(move || {
+                        serve_connection(stream, tx, stream_tx);
+                        connections.fetch_sub(1, Ordering::SeqCst);
+                    });
+                }
+                Err(e) => warn!("Command server: accept failed: {}", e),
+            }
+        }
+    });
+}
+
+fn serve_connection(stream: TcpStream, tx: Sender<PendingCommand>, stream_tx: Sender<StreamEvent>) {
+    let peer = stream.peer_addr().map(|a| a.to_string()).unwrap_or_default();
+    info!("Command client connected: {}", peer);
+    let Ok(mut writer) = stream.try_clone() else { return; };
+
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else { break; };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let Some(reply) = reply_to_line(&line, &tx, &stream_tx) else { break; };
+        let Ok(mut json) = serde_json::to_string(&reply) else { break; };
+        json.push('\n');
+        if writer.write_all(json.as_bytes()).is_err() {
+            break;
+        }
+    }
+    info!("Command client disconnected: {}", peer);
+}
+
+/// Выполняет строку протокола и готовит ответ; `None` — игра закрылась.
+fn reply_to_line(line: &str, tx: &Sender<PendingCommand>, stream_tx: &Sender<StreamEvent>) -> Option<CommandReply> {
+    let (id, command) = parse_command_line(line);
+    let reply = match command {
+        Err(e) => CommandReply::error(id, e),
+        Ok(ExternalCommand::StreamEvent(event)) => {
+            stream_tx.send(event).ok()?;
+            CommandReply::ok(id)
+        }
+        Ok(ExternalCommand::Action(action)) => {
+            let (reply_tx, reply_rx) = mpsc::channel();
+            tx.send(PendingCommand { action, reply: reply_tx }).ok()?;
+            match reply_rx.recv_timeout(REPLY_TIMEOUT) {
+                Ok(Ok(())) => CommandReply::ok(id),
+                Ok(Err(e)) => CommandReply::error(id, e),
+                Err(_) => CommandReply::error(id, "game did not respond"),
+            }
+        }
+    };
+    Some(reply)
+}
+
+fn handle_external_commands(inbox: Res<CommandInbox>, mut actions: GameActions) {
+    let Ok(inbox) = inbox.0.lock() else { return; };
+    while let Ok(PendingCommand { action, reply }) = inbox.try_recv() {
+        let result = actions.apply(&action);
+        if let Err(e) = &result {
+            warn!("External command {:?} rejected: {}", action, e);
+        }
+        let _ = reply.send(result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AppState;
+    use crate::headless::SimHarness;
+    use serde_json::json;
+
+    #[test]
+    fn parses_actions_and_stream_events() {
+        let (id, command) = parse_command_line(r#"{"id": 7, "cmd": "spawn_enemy", "kind": "Target_2"}"#);
+        assert_eq!(id, Some(json!(7)));
+        let Ok(ExternalCommand::Action(action)) = command else { panic!("{:?}", command) };
+        assert_eq!(action, GameAction::SpawnEnemy { kind: Some("Target_2".to_string()), count: 1, boss: false });
+
+        let (id, command) = parse_command_line(r#"{"cmd": "stream_event", "event": {"type": "follow", "user": "fan"}}"#);
+        assert_eq!(id, None);
+        let Ok(ExternalCommand::StreamEvent(StreamEvent::Follow { user })) = command else { panic!("{:?}", command) };
+        assert_eq!(user, "fan");
+    }
+
+    #[test]
+    fn rejects_malformed_lines_but_keeps_the_id() {
+        let (id, command) = parse_command_line("{not json");
+        assert_eq!(id, None);
+        assert!(command.unwrap_err().starts_with("invalid json"));
+
+        for line in [
+            r#"{"id": "a", "cmd": "explode"}"#,
+            r#"{"id": "a", "cmd": "grant_gold"}"#,
+            r#"{"id": "a", "cmd": "grant_gold", "amount": -5}"#,
+            r#"{"id": "a", "cmd": "stream_event", "event": {"type": "follow"}}"#,
+        ] {
+            let (id, command) = parse_command_line(line);
+            assert_eq!(id, Some(json!("a")), "{}", line);
+            assert!(command.unwrap_err().starts_with("invalid command"), "{}", line);
+        }
+    }
+
+    #[test]
+    fn replies_serialize_without_empty_fields() {
+        assert_eq!(serde_json::to_string(&CommandReply::ok(Some(json!(1)))).unwrap(), r#"{"id":1,"ok":true}"#);
+        assert_eq!(
+            serde_json::to_string(&CommandReply::error(None, "too many clients")).unwrap(),
+            r#"{"ok":false,"error":"too many clients"}"#
+        );
+    }
+
+    /// Прогоняет строки протокола через настоящий матч, как это делает клиентское соединение.
+    fn run_lines(lines: &[&str]) -> (Vec<String>, Vec<StreamEvent>) {
+        let mut sim = SimHarness::new();
+        let (tx, rx) = mpsc::channel();
+        let (stream_tx, stream_rx) = mpsc::channel();
+        sim.app.insert_resource(CommandInbox(Mutex::new(rx))).add_systems(Update, handle_external_commands);
+
+        let lines: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+        let client = std::thread::spawn(move || {
+            lines
+                .iter()
+                .map(|line| serde_json::to_string(&reply_to_line(line, &tx, &stream_tx).unwrap()).unwrap())
+                .collect::<Vec<_>>()
+        });
+        while !client.is_finished() {
+            sim.step(1);
+        }
+        (client.join().unwrap(), stream_rx.try_iter().collect())
+    }
+
+    #[test]
+    fn each_command_gets_its_own_reply() {
+        let (replies, events) = run_lines(&[
+            r#"{"id": 1, "cmd": "spawn_enemy", "kind": "Target_1", "count": 2}"#,
+            r#"{"id": 2, "cmd": "spawn_enemy", "kind": "Nobody"}"#,
+            r#"{"id": 3, "cmd": "spawn_enemy", "count": 0}"#,
+            r#"{"id": 4, "cmd": "grant_gold", "amount": 50}"#,
+            r#"{"id": 5, "cmd": "grant_gold", "amount": 0}"#,
+            r#"{"id": 6, "cmd": "place_tower", "tower": "laser", "pos": [0, 0, 0]}"#,
+            r#"{"id": 7, "cmd": "start_wave", "wave": 5}"#,
+            r#"{"id": 8, "cmd": "sell_tower", "tower": 999}"#,
+            r#"{"id": 9, "cmd": "stream_event", "event": {"type": "raid", "user": "friend", "viewers": 10}}"#,
+            r#"{"id": 10, "bogus": true}"#,
+        ]);
+        let expected = [
+            r#"{"id":1,"ok":true}"#,
+            r#"{"id":2,"ok":false,"error":"unknown enemy kind 'Nobody', expected one of [\"Target_1\", \"Target_2\"]"}"#,
+            r#"{"id":3,"ok":false,"error":"count must be in 1..=50"}"#,
+            r#"{"id":4,"ok":true}"#,
+            r#"{"id":5,"ok":false,"error":"amount must be positive"}"#,
+            r#"{"id":6,"ok":false,"error":"unknown tower id 'laser'"}"#,
+            r#"{"id":7,"ok":false,"error":"wave 5 is not next, current wave is 0"}"#,
+            r#"{"id":8,"ok":false,"error":"no tower #999"}"#,
+            r#"{"id":9,"ok":true}"#,
+        ];
+        assert_eq!(replies[..expected.len()], expected);
+        assert!(replies[expected.len()].starts_with(r#"{"id":10,"ok":false,"error":"invalid command"#), "{}", replies[9]);
+        assert!(matches!(&events[..], [StreamEvent::Raid { user, viewers: 10 }] if user == "friend"), "{:?}", events);
+    }
+
+    #[test]
+    fn game_commands_wait_for_the_match() {
+        let mut sim = SimHarness::new();
+        sim.app.world_mut().resource_mut::<NextState<AppState>>().set(AppState::Menu);
+        sim.step(1);
+        for action in [
+            GameAction::GrantGold { amount: 50 },
+            GameAction::SpawnEnemy { kind: None, count: 1, boss: false },
+            GameAction::StartWave { wave: None },
+        ] {
+            assert_eq!(sim.apply(&action), Err("game is not running".to_string()), "{:?}", action);
+        }
+    }
+}
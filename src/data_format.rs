@@ -0,0 +1,212 @@
+use crate::data_load::assets_dir;
+use anyhow::{Context, anyhow, bail};
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+use std::marker::PhantomData;
+use std::path::Path;
+
+/// Файлы данных в JSON, RON или TOML — формат по расширению, структуры те же.
+///
+/// Любой формат сначала превращается в `serde_json::Value`, а дальше всё как у JSON:
+/// поштучный разбор записей, `extends`, слияние паков. Поэтому в RON объекты пишутся как
+/// структуры `(id: "basic", range: 5.0)` или словари, а значения перечислений — строками,
+/// как в JSON: `behavior: "homing"`, `on_hit: [{"slow": (factor: 0.5, seconds: 2.0)}]`.
+pub struct DataAssetPlugin<A>(PhantomData<A>);
+
+impl<A> Default for DataAssetPlugin<A> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<A: Asset + DeserializeOwned> Plugin for DataAssetPlugin<A> {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<A>().register_asset_loader(DataAssetLoader::<A>(PhantomData));
+    }
+}
+
+pub const DATA_EXTENSIONS: [&str; 3] = ["json", "ron", "toml"];
+
+struct DataAssetLoader<A>(PhantomData<A>);
+
+impl<A: Asset + DeserializeOwned> AssetLoader for DataAssetLoader<A> {
+    type Asset = A;
+    type Settings = ();
+    type Error = anyhow::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<A, anyhow::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let text = String::from_utf8(bytes).context("file is not UTF-8")?;
+        parse_data(load_context.path(), &text)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &DATA_EXTENSIONS
+    }
+}
+
+/// Разбирает текст файла данных; формат — по расширению `path`.
+pub fn parse_data<T: DeserializeOwned>(path: &Path, text: &str) -> anyhow::Result<T> {
+    Ok(serde_json::from_value(parse_value(path, text)?)?)
+}
+
+fn parse_value(path: &Path, text: &str) -> anyhow::Result<serde_json::Value> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => Ok(serde_json::from_str(text)?),
+        Some("ron") => {
+            let value: ron::Value = ron::from_str(text)?;
+            reject_ron_identifiers(&value)?;
+            Ok(value.into_rust()?)
+        }
+        Some("toml") => {
+            let doc: toml_edit::DocumentMut = text.parse()?;
+            toml_table(doc.as_table())
+        }
+        _ => bail!("unknown data format, expected one of {:?}", DATA_EXTENSIONS),
+    }
+}
+
+/// `ron::Value` теряет имена вариантов: `Homing` стал бы `null`. Пусть лучше будет ошибка.
+fn reject_ron_identifiers(value: &ron::Value) -> anyhow::Result<()> {
+    match value {
+        ron::Value::Unit => bail!("bare identifiers and `()` are not supported, write enum values as strings"),
+        ron::Value::Option(Some(v)) => reject_ron_identifiers(v),
+        ron::Value::Seq(items) => items.iter().try_for_each(reject_ron_identifiers),
+        ron::Value::Map(map) => map.iter().try_for_each(|(k, v)| {
+            reject_ron_identifiers(k)?;
+            reject_ron_identifiers(v)
+        }),
+        _ => Ok(()),
+    }
+}
+
+fn toml_table(table: &toml_edit::Table) -> anyhow::Result<serde_json::Value> {
+    let mut map = serde_json::Map::new();
+    for (key, item) in table.iter() {
+        let value = match item {
+            toml_edit::Item::None => continue,
+            toml_edit::Item::Value(v) => toml_value(v),
+            toml_edit::Item::Table(t) => toml_table(t),
+            toml_edit::Item::ArrayOfTables(tables) => tables.iter().map(toml_table).collect(),
+        };
+        map.insert(key.to_string(), value.with_context(|| format!("key `{}`", key))?);
+    }
+    Ok(serde_json::Value::Object(map))
+}
+
+fn toml_value(value: &toml_edit::Value) -> anyhow::Result<serde_json::Value> {
+    use toml_edit::Value;
+    Ok(match value {
+        Value::String(s) => s.value().clone().into(),
+        Value::Integer(i) => (*i.value()).into(),
+        Value::Float(f) => serde_json::Number::from_f64(*f.value())
+            .ok_or_else(|| anyhow!("{} is not a finite number", f.value()))?
+            .into(),
+        Value::Boolean(b) => (*b.value()).into(),
+        Value::Datetime(d) => d.value().to_string().into(),
+        Value::Array(items) => items.iter().map(toml_value).collect::<anyhow::Result<_>>()?,
+        Value::InlineTable(t) => {
+            let fields = t.iter().map(|(k, v)| Ok((k.to_string(), toml_value(v)?)));
+            serde_json::Value::Object(fields.collect::<anyhow::Result<_>>()?)
+        }
+    })
+}
+
+/// Какой из вариантов `file` (`data/towers.json`) лежит в `assets`: `.json`, `.ron` или `.toml`.
+/// Если их несколько, берётся первый по этому порядку, об остальных — предупреждение.
+pub fn resolve_data_file(assets: &Path, file: &str) -> Option<String> {
+    let stem = file.rsplit_once('.').map_or(file, |(stem, _)| stem);
+    let mut found = DATA_EXTENSIONS
+        .iter()
+        .map(|ext| format!("{}.{}", stem, ext))
+        .filter(|path| assets.join(path).is_file());
+    let first = found.next()?;
+    let ignored: Vec<_> = found.collect();
+    if !ignored.is_empty() {
+        warn!("{} is used, ignoring {}", first, ignored.join(", "));
+    }
+    Some(first)
+}
+
+/// Файлы данных из папки `dir` внутри `assets`: пары (имя без расширения, путь), по алфавиту.
+/// Формат для каждого имени выбирается, как в [`resolve_data_file`]. Нет папки — пустой список.
+pub fn data_files_in(assets: &Path, dir: &str) -> Vec<(String, String)> {
+    let Ok(entries) = std::fs::read_dir(assets.join(dir)) else { return Vec::new(); };
+    let mut stems: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().and_then(|e| e.to_str()).is_some_and(|e| DATA_EXTENSIONS.contains(&e)))
+        .filter_map(|p| Some(p.file_stem()?.to_str()?.to_string()))
+        .collect();
+    stems.sort();
+    stems.dedup();
+    stems
+        .into_iter()
+        .filter_map(|stem| {
+            let file = resolve_data_file(assets, &format!("{}/{}.json", dir, stem))?;
+            Some((stem, file))
+        })
+        .collect()
+}
+
+/// Путь к варианту `file` из папки `assets`; если нет ни одного — сам `file`, и об этом скажет загрузка.
+pub fn data_file(file: &str) -> String {
+    resolve_data_file(&assets_dir(), file).unwrap_or_else(|| file.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_load::ProjectilesJsonFile;
+
+    const JSON: &str = r#"{"projectiles": [{"id": "frost_orb", "scale": 0.6, "behavior": "homing", "on_hit": [{"slow": {"factor": 0.5}}]}]}"#;
+
+    fn parse(file: &str, text: &str) -> anyhow::Result<serde_json::Value> {
+        parse_data(Path::new(file), text)
+    }
+
+    #[test]
+    fn ron_and_toml_give_the_same_value_as_json() {
+        let json = parse("projectiles.json", JSON).unwrap();
+        let ron = r#"(projectiles: [(id: "frost_orb", scale: 0.6, behavior: "homing", on_hit: [{"slow": (factor: 0.5)}])])"#;
+        assert_eq!(parse("projectiles.ron", ron).unwrap(), json);
+        let toml = r#"
+            [[projectiles]]
+            id = "frost_orb"
+            scale = 0.6
+            behavior = "homing"
+            on_hit = [{ slow = { factor = 0.5 } }]
+        "#;
+        assert_eq!(parse("projectiles.toml", toml).unwrap(), json);
+    }
+
+    #[test]
+    fn data_files_deserialize_from_any_format() {
+        let ron = r#"(projectiles: [(id: "frost_orb", on_hit: [{"slow": (factor: 0.5)}])])"#;
+        let file: ProjectilesJsonFile = parse_data(Path::new("data/projectiles.ron"), ron).unwrap();
+        assert_eq!(file.projectiles[0]["id"], "frost_orb");
+        assert_eq!(file.projectiles[0]["on_hit"][0]["slow"]["factor"], 0.5);
+    }
+
+    #[test]
+    fn ron_rejects_bare_identifiers() {
+        for ron in [r#"(behavior: Homing)"#, r#"(on_hit: [Some(())])"#, r#"{Homing: 1}"#] {
+            let err = parse("towers.ron", ron).unwrap_err();
+            assert!(err.to_string().contains("bare identifiers"), "{}: {}", ron, err);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_extensions_and_bad_numbers() {
+        assert!(parse("towers.yaml", "a: 1").unwrap_err().to_string().contains("unknown data format"));
+        assert!(parse("towers.toml", "speed = nan").is_err());
+    }
+}
@@ -1,92 +1,663 @@
+use crate::StartupStage::{Load, Processing};
+use crate::data_format::DataAssetPlugin;
+use crate::headless::Headless;
+use crate::loading::LoadingTracker;
+use crate::localization::{Locale, LocalizedText};
+use crate::mods::{ModPacks, merge_layers};
+use crate::projectile::{
+    DEFAULT_HIT_RADIUS, DEFAULT_LIFETIME, OnHitEffect, ProjectileBehavior, ProjectileDB, ProjectileDef,
+};
+use crate::target::{EnemyDef, TargetCatalog};
 use crate::tower::{TowerDB, TowerDef};
+use anyhow::{Context, anyhow, bail};
+use bevy::asset::io::file::FileAssetReader;
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
-use bevy_common_assets::json::JsonAssetPlugin;
+use bevy_inspector_egui::{bevy_egui, egui};
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Deserialize)]
-struct TowerDefJson {
+#[serde(deny_unknown_fields)]
+pub struct TowerDefJson {
     pub id: String,
+    /// Название и описание по языкам, см. [`LocalizedText`]; без названия UI покажет `id`.
+    #[serde(default)]
+    pub name: LocalizedText,
+    #[serde(default)]
+    pub description: LocalizedText,
     pub scene: String,
-    pub projectile_scene: String,
+    /// Id снаряда из `data/projectiles.json`.
+    pub projectile: String,
     pub fire_interval: f32,
     pub range: f32,
     pub damage: f32,
-    pub projectile_speed: f32,
-    pub projectile_scale: f32,
     pub offset: [f32; 3],
     pub shot_sound: String,
     pub shot_volume: f32
 }
 
-#[derive(serde::Deserialize, Asset, TypePath, Clone)]
-struct TowersJsonFile {
-    towers: Vec<TowerDefJson>,
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectileDefJson {
+    pub id: String,
+    pub scene: String,
+    pub speed: f32,
+    pub scale: f32,
+    #[serde(default = "default_lifetime")]
+    pub lifetime: f32,
+    #[serde(default = "default_hit_radius")]
+    pub hit_radius: f32,
+    #[serde(default)]
+    pub behavior: ProjectileBehavior,
+    #[serde(default)]
+    pub on_hit: Vec<OnHitEffect>,
+    #[serde(default)]
+    pub impact_scene: Option<String>,
+    #[serde(default)]
+    pub impact_sound: Option<String>,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EnemyDefJson {
+    /// По нему враг указывается в волнах и командах, без учёта регистра.
+    pub id: String,
+    /// Название и описание по языкам, как у башен; без названия UI покажет `id`.
+    #[serde(default)]
+    pub name: LocalizedText,
+    #[serde(default)]
+    pub description: LocalizedText,
+    /// glb с моделью и анимациями.
+    pub scene: String,
+    /// Номер анимации ходьбы в glb.
+    #[serde(default = "default_walk_animation")]
+    pub walk_animation: usize,
+    pub scale: f32,
+    pub health: f32,
+    pub speed: f32,
+}
+
+fn default_walk_animation() -> usize {
+    6
+}
+
+fn default_lifetime() -> f32 {
+    DEFAULT_LIFETIME
+}
+
+fn default_hit_radius() -> f32 {
+    DEFAULT_HIT_RADIUS
+}
+
+#[derive(serde::Deserialize, Asset, TypePath, Clone, Default)]
+pub struct ProjectilesJsonFile {
+    pub projectiles: Vec<serde_json::Value>,
+    /// Файл, из которого взята каждая запись, — после слияния паков.
+    #[serde(skip)]
+    pub origins: Vec<String>,
+}
+
+/// Записи разбираются по одной: ошибка в одной башне не роняет весь файл.
+///
+/// Запись с `"extends": "имя"` берёт поля у шаблона из `templates` или у другой
+/// башни с этим `id` и переопределяет только указанные.
+#[derive(serde::Deserialize, Asset, TypePath, Clone, Default)]
+pub struct TowersJsonFile {
+    /// Неполные записи, от которых наследуются башни; сами башнями не становятся.
+    #[serde(default)]
+    pub templates: BTreeMap<String, serde_json::Value>,
+    pub towers: Vec<serde_json::Value>,
+    /// Файл, из которого взята каждая запись, — после слияния паков.
+    #[serde(skip)]
+    pub origins: Vec<String>,
+}
+
+#[derive(serde::Deserialize, Asset, TypePath, Clone, Default)]
+pub struct EnemiesJsonFile {
+    pub enemies: Vec<serde_json::Value>,
+    /// Файл, из которого взята каждая запись, — после слияния паков.
+    #[serde(skip)]
+    pub origins: Vec<String>,
+}
+
+/// Базовый `towers.json` и его версии из паков, слитые в один; второе — конфликты между паками.
+pub fn merge_tower_files(layers: &[(&str, &TowersJsonFile)]) -> (TowersJsonFile, Vec<String>) {
+    let mut templates = BTreeMap::new();
+    for (_, file) in layers {
+        templates.extend(file.templates.iter().map(|(name, t)| (name.clone(), t.clone())));
+    }
+    let (towers, conflicts) = merge_layers("tower", layers.iter().map(|(path, f)| (*path, f.towers.as_slice())));
+    let (origins, towers) = towers.into_iter().unzip();
+    (TowersJsonFile { templates, towers, origins }, conflicts)
+}
+
+/// То же для `projectiles.json`.
+pub fn merge_projectile_files(layers: &[(&str, &ProjectilesJsonFile)]) -> (ProjectilesJsonFile, Vec<String>) {
+    let (projectiles, conflicts) =
+        merge_layers("projectile", layers.iter().map(|(path, f)| (*path, f.projectiles.as_slice())));
+    let (origins, projectiles) = projectiles.into_iter().unzip();
+    (ProjectilesJsonFile { projectiles, origins }, conflicts)
+}
+
+/// То же для `enemies.json`.
+pub fn merge_enemy_files(layers: &[(&str, &EnemiesJsonFile)]) -> (EnemiesJsonFile, Vec<String>) {
+    let (enemies, conflicts) = merge_layers("enemy", layers.iter().map(|(path, f)| (*path, f.enemies.as_slice())));
+    let (origins, enemies) = enemies.into_iter().unzip();
+    (EnemiesJsonFile { enemies, origins }, conflicts)
+}
+
+/// Ошибки в данных игры; записи с ошибками пропущены.
+#[derive(Resource, Debug, Default)]
+pub struct DataErrors(pub Vec<String>);
+
+/// Файл данных и его версии из паков, в порядке применения.
 #[derive(Resource)]
-struct TowersJsonHandle(Handle<TowersJsonFile>);
+struct DataLayers<A: Asset>(Vec<(String, Handle<A>)>);
+
+impl<A: Asset> DataLayers<A> {
+    fn load(file: &str, packs: &ModPacks, server: &AssetServer, tracker: &mut LoadingTracker) -> Self {
+        let layers = packs.layers(&assets_dir(), file).into_iter().map(|path| {
+            let handle: Handle<A> = server.load(path.clone());
+            tracker.track(path.clone(), handle.clone());
+            (path, handle)
+        });
+        Self(layers.collect())
+    }
+
+    /// Все слои, если каждый уже загружен.
+    fn get<'a>(&self, assets: &'a Assets<A>) -> Option<Vec<(&str, &'a A)>> {
+        self.0.iter().map(|(path, handle)| Some((path.as_str(), assets.get(handle)?))).collect()
+    }
+
+    fn paths(&self) -> Vec<&str> {
+        self.0.iter().map(|(path, _)| path.as_str()).collect()
+    }
+}
 
 pub struct DataLoadPlugin;
 
+/// Показывает [`DataErrors`] поверх игры.
+pub struct DataErrorsPanelPlugin;
+
+impl Plugin for DataErrorsPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DataErrorsPanelHidden>()
+            .add_systems(Update, show_new_data_errors.run_if(resource_changed::<DataErrors>))
+            .add_systems(
+                bevy_egui::EguiPrimaryContextPass,
+                ui_data_errors
+                    .run_if(resource_exists::<crate::egui_setup::EguiConfigured>)
+                    .run_if(|errors: Res<DataErrors>, hidden: Res<DataErrorsPanelHidden>| {
+                        !errors.0.is_empty() && !hidden.0
+                    }),
+            );
+    }
+}
+
 impl Plugin for DataLoadPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(JsonAssetPlugin::<TowersJsonFile>::new(&["json"]))
-            .add_systems(Startup, load_tower_json)
+        app.add_plugins(DataAssetPlugin::<TowersJsonFile>::default())
+            .add_plugins(DataAssetPlugin::<ProjectilesJsonFile>::default())
+            .add_plugins(DataAssetPlugin::<EnemiesJsonFile>::default())
+            .init_resource::<DataErrors>()
+            .add_systems(Startup, (load_projectile_json, load_tower_json, load_enemy_json).in_set(Load))
+            .add_systems(Update, build_target_catalog.run_if(layers_changed::<EnemiesJsonFile>).in_set(Processing))
             .add_systems(
                 Update,
-                build_tower_db_once
-                    .run_if(towers_json_ready)
-                    .run_if(not(resource_exists::<TowerDB>))
+                (
+                    build_projectile_db.run_if(layers_changed::<ProjectilesJsonFile>),
+                    // Башни хранят копии снарядов, поэтому пересобираются и вслед за ними.
+                    build_tower_db
+                        .run_if(layers_changed::<TowersJsonFile>.or(resource_exists_and_changed::<ProjectileDB>)),
+                )
+                    .chain()
+                    .in_set(Processing),
             );
     }
 }
 
-fn towers_json_ready(
-    handle: Option<Res<TowersJsonHandle>>,
-    assets: Res<Assets<TowersJsonFile>>,
-) -> bool {
-    match handle {
-        Some(h) => assets.get(&h.0).is_some(),      // true, если ассет уже в Assets
-        None => false,
-    }
+/// Один из слоёв загрузился или поменялся на диске (с фичей `hot_reload`).
+fn layers_changed<A: Asset>(mut events: EventReader<AssetEvent<A>>, layers: Option<Res<DataLayers<A>>>) -> bool {
+    let Some(layers) = layers else { return false; };
+    events.read().any(|ev| {
+        layers.0.iter().any(|(_, handle)| ev.is_loaded_with_dependencies(handle) || ev.is_modified(handle))
+    })
 }
 
-fn load_tower_json(mut commands: Commands, asset_server: Res<AssetServer>) {
+pub const TOWERS_JSON: &str = "data/towers.json";
+pub const PROJECTILES_JSON: &str = "data/projectiles.json";
+pub const ENEMIES_JSON: &str = "data/enemies.json";
+
+fn load_projectile_json(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    packs: Res<ModPacks>,
+    mut tracker: ResMut<LoadingTracker>,
+) {
+    let layers = DataLayers::<ProjectilesJsonFile>::load(PROJECTILES_JSON, &packs, &asset_server, &mut tracker);
+    commands.insert_resource(layers);
+}
+
+fn load_tower_json(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    packs: Res<ModPacks>,
+    mut tracker: ResMut<LoadingTracker>,
+) {
     info!("Load Tower Json");
-    let handle: Handle<TowersJsonFile> = asset_server.load("data/towers.json");
-    commands.insert_resource(TowersJsonHandle(handle));
+    let layers = DataLayers::<TowersJsonFile>::load(TOWERS_JSON, &packs, &asset_server, &mut tracker);
+    commands.insert_resource(layers);
 }
 
-fn build_tower_db_once(
+fn load_enemy_json(
     mut commands: Commands,
-    json_handle: Res<TowersJsonHandle>,
-    assets: Res<Assets<TowersJsonFile>>,
     asset_server: Res<AssetServer>,
+    packs: Res<ModPacks>,
+    mut tracker: ResMut<LoadingTracker>,
+) {
+    let layers = DataLayers::<EnemiesJsonFile>::load(ENEMIES_JSON, &packs, &asset_server, &mut tracker);
+    commands.insert_resource(layers);
+}
+
+/// Папка `assets`, из которой читает `AssetServer`.
+pub fn assets_dir() -> PathBuf {
+    FileAssetReader::get_base_path().join("assets")
+}
+
+fn require_positive(field: &str, value: f32) -> anyhow::Result<()> {
+    if value.is_nan() || value <= 0.0 {
+        bail!("field `{}` must be positive, got {}", field, value);
+    }
+    Ok(())
+}
+
+fn require_file(assets: &Path, field: &str, path: &str) -> anyhow::Result<()> {
+    if !assets.join(path).is_file() {
+        bail!("field `{}`: file assets/{} does not exist", field, path);
+    }
+    Ok(())
+}
+
+const EXTENDS: &str = "extends";
+
+/// Поля записи вместе с унаследованными по цепочке `extends`.
+fn resolve_extends(
+    file: &TowersJsonFile,
+    value: &serde_json::Value,
+    chain: &mut Vec<String>,
+) -> anyhow::Result<serde_json::Value> {
+    let serde_json::Value::Object(fields) = value else { return Ok(value.clone()); };
+    let Some(parent) = fields.get(EXTENDS) else { return Ok(value.clone()); };
+    let parent = parent.as_str().with_context(|| format!("field `{}` must be a string", EXTENDS))?;
+    if chain.iter().any(|name| name == parent) {
+        bail!("field `{}`: cycle {} -> {}", EXTENDS, chain.join(" -> "), parent);
+    }
+
+    let template = file.templates.get(parent);
+    let tower = file.towers.iter().find(|t| t.get("id").and_then(|id| id.as_str()) == Some(parent));
+    let base = match (template, tower) {
+        (Some(_), Some(_)) => bail!("field `{}`: '{}' is both a template and a tower", EXTENDS, parent),
+        (Some(base), None) | (None, Some(base)) => base,
+        (None, None) => bail!("field `{}`: unknown template or tower '{}'", EXTENDS, parent),
+    };
+    chain.push(parent.to_string());
+    let mut merged = resolve_extends(file, base, chain)?;
+    chain.pop();
+
+    let serde_json::Value::Object(merged_fields) = &mut merged else {
+        bail!("field `{}`: '{}' is not an object", EXTENDS, parent);
+    };
+    // `id` у каждой башни свой, шаблон его не передаёт.
+    merged_fields.remove("id");
+    for (key, field) in fields {
+        if key != EXTENDS {
+            merged_fields.insert(key.clone(), field.clone());
+        }
+    }
+    Ok(merged)
+}
+
+/// Разбирает записи по одной; ошибочные и повторные пропускаются. `origins` — файл
+/// каждой записи после слияния паков; без него все записи из `file`.
+fn parse_entries<T>(
+    file: &str,
+    kind: &str,
+    values: &[serde_json::Value],
+    origins: &[String],
+    mut parse: impl FnMut(&serde_json::Value) -> anyhow::Result<T>,
+    id: impl Fn(&T) -> &str,
+) -> (Vec<T>, Vec<anyhow::Error>) {
+    let mut entries: Vec<T> = Vec::new();
+    let mut errors = Vec::new();
+    for (i, value) in values.iter().enumerate() {
+        let file = origins.get(i).map_or(file, String::as_str);
+        // В ошибке — файл, запись и поле.
+        let label = match value.get("id").and_then(|id| id.as_str()) {
+            Some(id) => format!("{}: {} '{}'", file, kind, id),
+            None => format!("{}: {} #{}", file, kind, i),
+        };
+        match parse(value).context(label) {
+            Ok(j) if entries.iter().any(|t| id(t) == id(&j)) => errors.push(anyhow!(
+                "{}: {} '{}': duplicate id, the first definition is used",
+                file,
+                kind,
+                id(&j)
+            )),
+            Ok(j) => entries.push(j),
+            Err(e) => errors.push(e),
+        }
+    }
+    (entries, errors)
+}
+
+/// Разбирает и проверяет все башни; `projectiles` — id известных снарядов.
+pub fn parse_towers(
+    file: &TowersJsonFile,
+    projectiles: &[String],
+    assets: &Path,
+) -> (Vec<TowerDefJson>, Vec<anyhow::Error>) {
+    let parse = |value: &serde_json::Value| -> anyhow::Result<TowerDefJson> {
+        let mut chain = value.get("id").and_then(|id| id.as_str()).map(str::to_string).into_iter().collect();
+        let j: TowerDefJson = serde_json::from_value(resolve_extends(file, value, &mut chain)?)?;
+        require_positive("damage", j.damage)?;
+        require_positive("fire_interval", j.fire_interval)?;
+        require_positive("range", j.range)?;
+        require_file(assets, "scene", &j.scene)?;
+        require_file(assets, "shot_sound", &j.shot_sound)?;
+        if !projectiles.contains(&j.projectile) {
+            bail!("field `projectile`: unknown projectile '{}'", j.projectile);
+        }
+        Ok(j)
+    };
+    parse_entries(TOWERS_JSON, "tower", &file.towers, &file.origins, parse, |t| &t.id)
+}
+
+/// Разбирает и проверяет все снаряды.
+pub fn parse_projectiles(file: &ProjectilesJsonFile, assets: &Path) -> (Vec<ProjectileDefJson>, Vec<anyhow::Error>) {
+    let parse = |value: &serde_json::Value| -> anyhow::Result<ProjectileDefJson> {
+        let j: ProjectileDefJson = serde_json::from_value(value.clone())?;
+        require_positive("speed", j.speed)?;
+        require_positive("scale", j.scale)?;
+        require_positive("lifetime", j.lifetime)?;
+        require_positive("hit_radius", j.hit_radius)?;
+        require_file(assets, "scene", &j.scene)?;
+        if let Some(scene) = &j.impact_scene {
+            require_file(assets, "impact_scene", scene)?;
+        }
+        if let Some(sound) = &j.impact_sound {
+            require_file(assets, "impact_sound", sound)?;
+        }
+        for effect in &j.on_hit {
+            match *effect {
+                OnHitEffect::Splash { radius, damage } => {
+                    require_positive("on_hit.splash.radius", radius)?;
+                    require_positive("on_hit.splash.damage", damage)?;
+                }
+                OnHitEffect::Slow { factor, seconds } => {
+                    if !(0.0..1.0).contains(&factor) {
+                        bail!("field `on_hit.slow.factor` must be in [0, 1), got {}", factor);
+                    }
+                    require_positive("on_hit.slow.seconds", seconds)?;
+                }
+            }
+        }
+        Ok(j)
+    };
+    parse_entries(PROJECTILES_JSON, "projectile", &file.projectiles, &file.origins, parse, |p| &p.id)
+}
+
+/// Разбирает и проверяет всех врагов.
+pub fn parse_enemies(file: &EnemiesJsonFile, assets: &Path) -> (Vec<EnemyDefJson>, Vec<anyhow::Error>) {
+    let parse = |value: &serde_json::Value| -> anyhow::Result<EnemyDefJson> {
+        let j: EnemyDefJson = serde_json::from_value(value.clone())?;
+        require_positive("scale", j.scale)?;
+        require_positive("health", j.health)?;
+        require_positive("speed", j.speed)?;
+        require_file(assets, "scene", &j.scene)?;
+        Ok(j)
+    };
+    parse_entries(ENEMIES_JSON, "enemy", &file.enemies, &file.origins, parse, |e| &e.id)
+}
+
+/// Заменяет прежние ошибки файлов `files` новыми; конфликты паков только в лог.
+fn report_data_errors(files: &[&str], invalid: Vec<anyhow::Error>, conflicts: Vec<String>, errors: &mut DataErrors) {
+    for c in conflicts {
+        warn!("{}", c);
+    }
+    errors.0.retain(|e| !files.iter().any(|file| e.starts_with(file)));
+    for e in invalid {
+        let e = format!("{:#}", e);
+        error!("{}", e);
+        errors.0.push(e);
+    }
+}
+
+/// Модели и звуки, на которые ссылаются данные.
+#[derive(SystemParam)]
+struct DataAssets<'w> {
+    server: Res<'w, AssetServer>,
+    headless: Option<Res<'w, Headless>>,
+    tracker: ResMut<'w, LoadingTracker>,
+}
+
+impl DataAssets<'_> {
+    /// Без презентации модели и звуки не нужны, да и грузить их некому — хэндл пустой.
+    fn load<A: Asset>(&mut self, path: &str, owner: &str) -> Handle<A> {
+        if self.headless.is_some() {
+            return Handle::default();
+        }
+        let handle: Handle<A> = self.server.load(path.to_string());
+        self.tracker.track(format!("{} ({})", path, owner), handle.clone());
+        handle
+    }
+
+    fn load_scene(&mut self, path: &str, owner: &str) -> Handle<Scene> {
+        self.load(&format!("{}#Scene0", path), owner)
+    }
+}
+
+fn build_projectile_db(
+    mut commands: Commands,
+    layers: Res<DataLayers<ProjectilesJsonFile>>,
+    assets: Res<Assets<ProjectilesJsonFile>>,
+    mut data_assets: DataAssets,
+    mut errors: ResMut<DataErrors>,
+) {
+    let Some(files) = layers.get(&assets) else { return; };
+    let (file, conflicts) = merge_projectile_files(&files);
+    let (projectiles, invalid) = parse_projectiles(&file, &assets_dir());
+    report_data_errors(&layers.paths(), invalid, conflicts, &mut errors);
+
+    let mut defs = HashMap::new();
+    for j in projectiles {
+        let def = ProjectileDef {
+            id: j.id.clone(),
+            scene: data_assets.load_scene(&j.scene, &j.id),
+            speed: j.speed,
+            scale: j.scale,
+            lifetime: j.lifetime,
+            hit_radius: j.hit_radius,
+            behavior: j.behavior,
+            on_hit: j.on_hit,
+            impact_scene: j.impact_scene.map(|scene| data_assets.load_scene(&scene, &j.id)),
+            impact_sound: j.impact_sound.map(|sound| data_assets.load(&sound, &j.id)),
+        };
+        defs.insert(j.id, def);
+    }
+
+    info!("ProjectileDB built from {}: {} projectiles", layers.paths().join(", "), defs.len());
+    commands.insert_resource(ProjectileDB { defs });
+}
+
+/// Пересобирает `TowerDB`; живые башни подхватывают новые статы в [`TowerPlugin`](crate::tower::TowerPlugin).
+fn build_tower_db(
+    mut commands: Commands,
+    layers: Res<DataLayers<TowersJsonFile>>,
+    assets: Res<Assets<TowersJsonFile>>,
+    projectiles: Option<Res<ProjectileDB>>,
+    mut data_assets: DataAssets,
+    mut errors: ResMut<DataErrors>,
 ) {
     info!("Load Tower DB");
-    let file = assets.get(&json_handle.0).expect("json must be loaded by run_if");
+    let Some(files) = layers.get(&assets) else { return; };
+    // Без снарядов башни не собрать; соберём, когда загрузится их файл.
+    let Some(projectiles) = projectiles else { return; };
+
+    let known: Vec<String> = projectiles.defs.keys().cloned().collect();
+    let (file, conflicts) = merge_tower_files(&files);
+    let (towers, invalid) = parse_towers(&file, &known, &assets_dir());
+    report_data_errors(&layers.paths(), invalid, conflicts, &mut errors);
 
     let mut defs = HashMap::new();
-    for j in &file.towers {
-        let scene: Handle<Scene>      = asset_server.load(format!("{}#Scene0", j.scene));
-        let proj_scene: Handle<Scene> = asset_server.load(format!("{}#Scene0", j.projectile_scene));
-        let shot_sound: Handle<AudioSource> = asset_server.load(j.shot_sound.clone());
+    for j in towers {
         defs.insert(j.id.clone(), TowerDef {
             id: j.id.clone(),
+            name: j.name,
+            description: j.description,
             damage: j.damage,
-            scene,
-            projectile_scene: proj_scene,
+            scene: data_assets.load_scene(&j.scene, &j.id),
             fire_interval: j.fire_interval,
             range: j.range,
-            projectile_speed: j.projectile_speed,
-            projectile_scale: j.projectile_scale,
+            projectile: projectiles.defs[&j.projectile].clone(),
             offset: Vec3::from_array(j.offset),
-            shot_sound,
+            shot_sound: data_assets.load(&j.shot_sound, &j.id),
             shot_volume: j.shot_volume
         });
     }
 
+    info!("TowerDB built from {}: {} towers", layers.paths().join(", "), defs.len());
     commands.insert_resource(TowerDB { defs });
-    info!("TowerDB built from data/towers.json");
+}
+
+/// Пересобирает каталог врагов. Идущие цели хранят id вида, а не номер в каталоге, и остаются собой;
+/// статы и модель у них прежние, новые берут только следующие цели.
+fn build_target_catalog(
+    mut commands: Commands,
+    layers: Res<DataLayers<EnemiesJsonFile>>,
+    assets: Res<Assets<EnemiesJsonFile>>,
+    mut data_assets: DataAssets,
+    mut errors: ResMut<DataErrors>,
+) {
+    let Some(files) = layers.get(&assets) else { return; };
+    let (file, conflicts) = merge_enemy_files(&files);
+    let (enemies, invalid) = parse_enemies(&file, &assets_dir());
+    report_data_errors(&layers.paths(), invalid, conflicts, &mut errors);
+
+    let entries: Vec<_> = enemies
+        .into_iter()
+        .map(|j| EnemyDef {
+            scene: data_assets.load_scene(&j.scene, &j.id),
+            walk: data_assets.load(&format!("{}#Animation{}", j.scene, j.walk_animation), &j.id),
+            id: j.id,
+            name: j.name,
+            description: j.description,
+            scale: j.scale,
+            health: j.health,
+            speed: j.speed,
+        })
+        .collect();
+
+    info!("TargetCatalog built from {}: {} enemies", layers.paths().join(", "), entries.len());
+    commands.insert_resource(TargetCatalog::new(entries));
+}
+
+#[derive(Resource, Default)]
+struct DataErrorsPanelHidden(bool);
+
+fn show_new_data_errors(mut hidden: ResMut<DataErrorsPanelHidden>) {
+    hidden.0 = false;
+}
+
+fn ui_data_errors(
+    mut egui_ctx: bevy_egui::EguiContexts,
+    errors: Res<DataErrors>,
+    mut hidden: ResMut<DataErrorsPanelHidden>,
+    locale: Res<Locale>,
+) {
+    let Ok(ctx) = egui_ctx.ctx_mut() else { return; };
+
+    egui::Area::new(egui::Id::new("data_errors_area"))
+        .anchor(egui::Align2::CENTER_TOP, [0.0, 40.0])
+        .show(ctx, |ui| {
+            let frame = egui::Frame::window(&ctx.style())
+                .fill(ui.visuals().panel_fill)
+                .corner_radius(egui::CornerRadius::same(6))
+                .inner_margin(egui::Margin::symmetric(10, 8))
+                .stroke(ui.visuals().widgets.noninteractive.bg_stroke);
+
+            egui::Frame::show(frame, ui, |ui| {
+                ui.heading(locale.t("data_errors.title"));
+                ui.label(locale.t("data_errors.skipped"));
+                ui.separator();
+                for e in &errors.0 {
+                    ui.colored_label(egui::Color32::from_rgb(230, 80, 60), e);
+                }
+                ui.separator();
+                if ui.button(locale.t("data_errors.hide")).clicked() {
+                    hidden.0 = true;
+                }
+            });
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn towers(templates: serde_json::Value, towers: serde_json::Value) -> TowersJsonFile {
+        serde_json::from_value(json!({ "templates": templates, "towers": towers })).unwrap()
+    }
+
+    /// Как в `parse_towers`: цепочка начинается с самой башни.
+    fn resolve(file: &TowersJsonFile, i: usize) -> anyhow::Result<serde_json::Value> {
+        let value = &file.towers[i];
+        let mut chain = vec![value["id"].as_str().unwrap().to_string()];
+        resolve_extends(file, value, &mut chain)
+    }
+
+    #[test]
+    fn extends_merges_template_and_tower_fields() {
+        let file = towers(
+            json!({ "cannon": { "projectile": "shell", "range": 5.0 } }),
+            json!([
+                { "id": "basic", "extends": "cannon", "damage": 10.0 },
+                { "id": "long", "extends": "basic", "range": 8.0 },
+            ]),
+        );
+        let long = resolve(&file, 1).unwrap();
+        assert_eq!(long, json!({ "id": "long", "projectile": "shell", "range": 8.0, "damage": 10.0 }));
+    }
+
+    #[test]
+    fn extends_detects_cycles() {
+        let file = towers(
+            json!({}),
+            json!([
+                { "id": "a", "extends": "b" },
+                { "id": "b", "extends": "a" },
+                { "id": "c", "extends": "c" },
+            ]),
+        );
+        assert_eq!(resolve(&file, 0).unwrap_err().to_string(), "field `extends`: cycle a -> b -> a");
+        assert_eq!(resolve(&file, 2).unwrap_err().to_string(), "field `extends`: cycle c -> c");
+    }
+
+    #[test]
+    fn extends_reports_unknown_and_ambiguous_parents() {
+        let file = towers(
+            json!({ "basic": {} }),
+            json!([
+                { "id": "basic" },
+                { "id": "a", "extends": "missing" },
+                { "id": "b", "extends": "basic" },
+                { "id": "c", "extends": 1 },
+            ]),
+        );
+        assert_eq!(resolve(&file, 1).unwrap_err().to_string(), "field `extends`: unknown template or tower 'missing'");
+        assert_eq!(resolve(&file, 2).unwrap_err().to_string(), "field `extends`: 'basic' is both a template and a tower");
+        assert_eq!(resolve(&file, 3).unwrap_err().to_string(), "field `extends` must be a string");
+    }
 }
@@ -0,0 +1,47 @@
+use crate::target::TargetLeaked;
+use bevy::prelude::*;
+
+pub struct EconomyPlugin;
+
+impl Plugin for EconomyPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Gold(STARTING_GOLD))
+            .insert_resource(Lives(STARTING_LIVES))
+            .add_event::<GrantGoldEvent>()
+            .add_systems(FixedUpdate, (grant_gold, lose_lives));
+    }
+}
+
+/// Без уровня; уровень задаёт свои, см. [`crate::level::LevelDef`].
+pub const STARTING_GOLD: u32 = 100;
+pub const STARTING_LIVES: u32 = 20;
+
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct Gold(pub u32);
+
+/// Сколько ещё целей может дойти до конца карты.
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct Lives(pub u32);
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct GrantGoldEvent {
+    pub amount: u32,
+}
+
+fn grant_gold(mut ev: EventReader<GrantGoldEvent>, mut gold: ResMut<Gold>) {
+    for GrantGoldEvent { amount } in ev.read() {
+        gold.0 = gold.0.saturating_add(*amount);
+        info!("Granted {} gold, total {}", amount, gold.0);
+    }
+}
+
+fn lose_lives(mut ev: EventReader<TargetLeaked>, mut lives: ResMut<Lives>) {
+    let leaked = ev.read().count() as u32;
+    if leaked == 0 || lives.0 == 0 {
+        return;
+    }
+    lives.0 = lives.0.saturating_sub(leaked);
+    if lives.0 == 0 {
+        warn!("No lives left");
+    }
+}
@@ -0,0 +1,72 @@
+use crate::AppState;
+use bevy::app::{RunFixedMainLoop, RunFixedMainLoopSystem};
+use bevy::prelude::*;
+
+/// Симуляция идёт в `FixedUpdate` с постоянным шагом, UI и камера — в `Update`.
+///
+/// Логика двигает `Transform` как обычно; на время кадра этот плагин подменяет позицию
+/// сущностей с [`TranslationInterpolation`] на промежуточную между двумя последними
+/// шагами, а перед следующими шагами возвращает настоящую.
+pub struct FixedStepPlugin;
+
+impl Plugin for FixedStepPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Time::<Fixed>::from_hz(SIMULATION_HZ))
+            .init_resource::<SimulationTick>()
+            .add_systems(FixedLast, advance_tick.run_if(in_state(AppState::InGame)))
+            .add_systems(
+                RunFixedMainLoop,
+                restore_simulated_translation.in_set(RunFixedMainLoopSystem::BeforeFixedMainLoop),
+            )
+            .add_systems(FixedFirst, remember_previous_translation)
+            .add_systems(
+                RunFixedMainLoop,
+                interpolate_translation.in_set(RunFixedMainLoopSystem::AfterFixedMainLoop),
+            );
+    }
+}
+
+pub const SIMULATION_HZ: f64 = 60.0;
+
+/// Сколько шагов симуляции прошло в `InGame`; пауза и меню не считаются.
+/// Во время шага — номер текущего шага.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SimulationTick(pub u64);
+
+fn advance_tick(mut tick: ResMut<SimulationTick>) {
+    tick.0 += 1;
+}
+
+/// Плавное движение между шагами симуляции. `None` — шагов ещё не было.
+#[derive(Component, Default, Debug, Clone, Copy)]
+pub struct TranslationInterpolation {
+    previous: Option<Vec3>,
+    current: Option<Vec3>,
+}
+
+fn restore_simulated_translation(mut q: Query<(&mut Transform, &TranslationInterpolation)>) {
+    for (mut transform, interp) in &mut q {
+        if let Some(current) = interp.current {
+            transform.translation = current;
+        }
+    }
+}
+
+fn remember_previous_translation(mut q: Query<(&Transform, &mut TranslationInterpolation)>) {
+    for (transform, mut interp) in &mut q {
+        interp.previous = Some(transform.translation);
+    }
+}
+
+fn interpolate_translation(
+    mut q: Query<(&mut Transform, &mut TranslationInterpolation)>,
+    fixed: Res<Time<Fixed>>,
+) {
+    let alpha = fixed.overstep_fraction();
+    for (mut transform, mut interp) in &mut q {
+        let current = transform.translation;
+        interp.current = Some(current);
+        let previous = interp.previous.unwrap_or(current);
+        transform.translation = previous.lerp(current, alpha);
+    }
+}
@@ -0,0 +1,233 @@
+use crate::AppState;
+use crate::economy::GrantGoldEvent;
+use crate::level::ActiveLevel;
+use crate::target::{SpawnTargetEvent, TargetCatalog};
+use crate::tower::{
+    FreezeTowersEvent, PlaceTowerEvent, SellTowerEvent, SponsorTowerEvent, TowerDB, TowerId, UpgradeTowerEvent,
+};
+use crate::wave::{StartWaveEvent, WaveState};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const MAX_SPAWN_COUNT: u32 = 50;
+const MAX_FREEZE_SECONDS: f32 = 30.0;
+
+/// Действие над игрой, которое может прийти извне: TCP-команда, реакция на стрим и т.п.
+/// В JSON тег — поле `cmd`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum GameAction {
+    SpawnEnemy {
+        #[serde(default)]
+        kind: Option<String>,
+        #[serde(default = "one")]
+        count: u32,
+        #[serde(default)]
+        boss: bool,
+    },
+    GrantGold {
+        amount: u32,
+    },
+    /// `wave` — номер волны, которую ждёт зритель (`!wave 3`); без него — следующая.
+    StartWave {
+        #[serde(default)]
+        wave: Option<u32>,
+    },
+    PlaceTower {
+        tower: String,
+        pos: [f32; 3],
+    },
+    FreezeTowers {
+        seconds: f32,
+    },
+    /// Зритель оплатил башню, стример поставит её сам. Если `tower` не задан,
+    /// берётся из аргумента команды (`!sponsor sniper`, ввод к награде).
+    SponsorTower {
+        #[serde(default)]
+        tower: Option<String>,
+    },
+    /// `tower` — [`TowerId`] башни.
+    UpgradeTower {
+        tower: u32,
+    },
+    SellTower {
+        tower: u32,
+    },
+}
+
+/// Регистрирует [`ActionApplied`]; нужен всем, кто использует [`GameActions`].
+pub struct GameActionPlugin;
+
+impl Plugin for GameActionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ActionApplied>();
+    }
+}
+
+/// Действие прошло проверку и отправлено в игру — по этим событиям пишется реплей.
+#[derive(Event, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActionApplied {
+    pub action: GameAction,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub viewer: Option<String>,
+}
+
+impl GameAction {
+    /// Подставляет аргумент команды зрителя в незаполненные поля действия.
+    pub fn with_argument(&self, argument: Option<&str>) -> GameAction {
+        match (self, argument) {
+            (GameAction::SponsorTower { tower: None }, Some(arg)) => GameAction::SponsorTower {
+                tower: Some(arg.to_lowercase()),
+            },
+            (GameAction::StartWave { wave: None }, Some(arg)) => GameAction::StartWave {
+                wave: arg.trim_start_matches('#').parse().ok(),
+            },
+            _ => self.clone(),
+        }
+    }
+}
+
+fn one() -> u32 {
+    1
+}
+
+/// Проверяет [`GameAction`] и превращает его в те же события, что пишет игровой UI.
+#[derive(SystemParam)]
+pub struct GameActions<'w, 's> {
+    state: Res<'w, State<AppState>>,
+    catalog: Res<'w, TargetCatalog>,
+    wave: Res<'w, WaveState>,
+    tower_db: Option<Res<'w, TowerDB>>,
+    level: Option<Res<'w, ActiveLevel>>,
+    spawn_target: EventWriter<'w, SpawnTargetEvent>,
+    grant_gold: EventWriter<'w, GrantGoldEvent>,
+    start_wave: EventWriter<'w, StartWaveEvent>,
+    place_tower: EventWriter<'w, PlaceTowerEvent>,
+    freeze_towers: EventWriter<'w, FreezeTowersEvent>,
+    sponsor_tower: EventWriter<'w, SponsorTowerEvent>,
+    upgrade_tower: EventWriter<'w, UpgradeTowerEvent>,
+    sell_tower: EventWriter<'w, SellTowerEvent>,
+    applied: EventWriter<'w, ActionApplied>,
+    towers: Query<'w, 's, (Entity, &'static TowerId)>,
+}
+
+impl GameActions<'_, '_> {
+    pub fn apply(&mut self, action: &GameAction) -> Result<(), String> {
+        self.apply_as(action, None)
+    }
+
+    /// То же, что [`apply`](Self::apply), но с указанием зрителя, который вызвал действие.
+    pub fn apply_as(&mut self, action: &GameAction, viewer: Option<&str>) -> Result<(), String> {
+        self.apply_unlogged(action, viewer)?;
+        self.applied.write(ActionApplied { action: action.clone(), viewer: viewer.map(str::to_string) });
+        Ok(())
+    }
+
+    fn find_tower(&self, id: u32) -> Result<Entity, String> {
+        self.towers
+            .iter()
+            .find(|(_, tower_id)| tower_id.0 == id)
+            .map(|(e, _)| e)
+            .ok_or_else(|| format!("no tower #{}", id))
+    }
+
+    fn apply_unlogged(&mut self, action: &GameAction, viewer: Option<&str>) -> Result<(), String> {
+        let in_game = *self.state.get() == AppState::InGame;
+        match action {
+            GameAction::SpawnEnemy { kind, count, boss } => {
+                let kind = match kind {
+                    None => None,
+                    Some(name) => Some(self.catalog.find(name).ok_or_else(|| {
+                        let known: Vec<_> = self.catalog.names().collect();
+                        format!("unknown enemy kind '{}', expected one of {:?}", name, known)
+                    })?),
+                };
+                if !in_game {
+                    return Err("game is not running".to_string());
+                }
+                if *count == 0 || *count > MAX_SPAWN_COUNT {
+                    return Err(format!("count must be in 1..={}", MAX_SPAWN_COUNT));
+                }
+                for _ in 0..*count {
+                    self.spawn_target.write(SpawnTargetEvent {
+                        kind,
+                        boss: *boss,
+                        viewer: viewer.map(str::to_string),
+                        wave: None,
+                    });
+                }
+            }
+            GameAction::GrantGold { amount } => {
+                if *amount == 0 {
+                    return Err("amount must be positive".to_string());
+                }
+                if !in_game {
+                    return Err("game is not running".to_string());
+                }
+                self.grant_gold.write(GrantGoldEvent { amount: *amount });
+            }
+            GameAction::StartWave { wave } => {
+                if !in_game {
+                    return Err("game is not running".to_string());
+                }
+                if let Some(wave) = wave
+                    && *wave != self.wave.index + 1
+                {
+                    return Err(format!("wave {} is not next, current wave is {}", wave, self.wave.index));
+                }
+                self.start_wave.write(StartWaveEvent);
+            }
+            GameAction::PlaceTower { tower, pos } => {
+                let pos = Vec3::from_array(*pos);
+                let Some(db) = self.tower_db.as_ref() else {
+                    return Err("tower data is not loaded yet".to_string());
+                };
+                if !pos.is_finite() {
+                    return Err("pos must be finite".to_string());
+                }
+                if !db.defs.contains_key(tower) {
+                    return Err(format!("unknown tower id '{}'", tower));
+                }
+                if let Some(level) = &self.level
+                    && !level.can_build(pos)
+                {
+                    return Err(format!("pos {:?} is outside the build zones of level '{}'", pos.to_array(), level.id));
+                }
+                self.place_tower.write(PlaceTowerEvent { id: tower.clone(), pos, owner: viewer.map(str::to_string) });
+            }
+            GameAction::FreezeTowers { seconds } => {
+                if !seconds.is_finite() || *seconds <= 0.0 || *seconds > MAX_FREEZE_SECONDS {
+                    return Err(format!("seconds must be in (0, {}]", MAX_FREEZE_SECONDS));
+                }
+                if !in_game {
+                    return Err("game is not running".to_string());
+                }
+                self.freeze_towers.write(FreezeTowersEvent { seconds: *seconds });
+            }
+            GameAction::SponsorTower { tower } => {
+                let Some(viewer) = viewer else {
+                    return Err("sponsor_tower needs a viewer".to_string());
+                };
+                let Some(db) = self.tower_db.as_ref() else {
+                    return Err("tower data is not loaded yet".to_string());
+                };
+                let mut known: Vec<_> = db.defs.keys().cloned().collect();
+                known.sort();
+                let Some(tower) = tower.as_ref().filter(|t| db.defs.contains_key(*t)) else {
+                    return Err(format!("unknown tower {:?}, expected one of {:?}", tower, known));
+                };
+                self.sponsor_tower.write(SponsorTowerEvent { viewer: viewer.to_string(), tower: tower.clone() });
+            }
+            GameAction::UpgradeTower { tower } => {
+                let tower = self.find_tower(*tower)?;
+                self.upgrade_tower.write(UpgradeTowerEvent { tower });
+            }
+            GameAction::SellTower { tower } => {
+                let tower = self.find_tower(*tower)?;
+                self.sell_tower.write(SellTowerEvent { tower });
+            }
+        }
+        Ok(())
+    }
+}
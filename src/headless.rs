@@ -0,0 +1,213 @@
+use crate::game_action::{GameAction, GameActions};
+use crate::loading::LoadingProgress;
+use crate::replay::ReplayConfig;
+use crate::tower::{PlaceTowerEvent, Tower};
+use crate::viewer_stats::{ViewerStatsPath, ViewerStatsPlugin};
+use crate::wave::StartWaveEvent;
+use crate::target::{AmbientSpawn, SpawnTargetEvent, TargetKilled, TargetLeaked};
+use crate::rng::GameRng;
+use crate::save_game::{GameSnapshot, PendingLoad, SaveGame};
+use crate::{AppState, SimulationPlugins};
+use bevy::asset::AssetPlugin;
+use bevy::ecs::system::SystemState;
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use bevy::time::TimeUpdateStrategy;
+use bevy::transform::TransformPlugin;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Игра без окна, рендера и звука: `MinimalPlugins` плюс то, без чего не живёт симуляция.
+///
+/// Башни, цели и снаряды остаются сущностями только со статами — модели вешают
+/// плагины из [`PresentationPlugins`](crate::PresentationPlugins), которых тут нет.
+/// Время идёт фиксированными шагами по `step` за кадр, независимо от реального.
+pub struct HeadlessPlugin {
+    pub step: Duration,
+}
+
+impl Default for HeadlessPlugin {
+    fn default() -> Self {
+        Self { step: Duration::from_secs_f64(1.0 / 60.0) }
+    }
+}
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MinimalPlugins)
+            .add_plugins((StatesPlugin, TransformPlugin, AssetPlugin::default()))
+            .insert_resource(Headless)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(self.step))
+            .init_resource::<SimCounters>()
+            .add_systems(Last, count_kills_and_leaks);
+    }
+}
+
+/// Игра запущена без презентации: ассеты моделей и звуков не грузятся.
+#[derive(Resource)]
+pub struct Headless;
+
+/// Итоги симуляции для тестов и утилит.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SimCounters {
+    pub spawned: u32,
+    pub kills: u32,
+    pub leaks: u32,
+}
+
+fn count_kills_and_leaks(
+    mut counters: ResMut<SimCounters>,
+    mut spawned: EventReader<SpawnTargetEvent>,
+    mut killed: EventReader<TargetKilled>,
+    mut leaked: EventReader<TargetLeaked>,
+) {
+    counters.spawned += spawned.read().count() as u32;
+    counters.kills += killed.read().count() as u32;
+    counters.leaks += leaked.read().count() as u32;
+}
+
+const LOAD_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Headless-приложение, которое тест крутит по кадрам.
+///
+/// ```ignore
+/// let mut sim = SimHarness::new();
+/// sim.start_wave();
+/// sim.step(60 * 90);
+/// assert_eq!(sim.counters().kills + sim.counters().leaks, 5);
+/// ```
+pub struct SimHarness {
+    pub app: App,
+}
+
+impl Default for SimHarness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SimHarness {
+    /// Собирает приложение с сидом 0, ждёт загрузки `data/` и переводит игру в `InGame`.
+    pub fn new() -> Self {
+        Self::with_seed(0)
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_replay(seed, ReplayConfig::default())
+    }
+
+    /// Как [`with_seed`](Self::with_seed), но с записью и/или воспроизведением реплея;
+    /// сид воспроизводимого реплея заменяет `seed`.
+    pub fn with_replay(seed: u64, replay: ReplayConfig) -> Self {
+        let mut app = App::new();
+        app.insert_resource(replay)
+            .add_plugins(HeadlessPlugin::default())
+            .add_plugins(SimulationPlugins.build().disable::<ViewerStatsPlugin>());
+        Self::start(app, seed)
+    }
+
+    /// Как [`with_seed`](Self::with_seed), но со статистикой зрителей в файле `path`.
+    /// Остальные конструкторы её выключают, чтобы не трогать `viewer_stats.json`.
+    pub fn with_viewer_stats(seed: u64, path: impl Into<PathBuf>) -> Self {
+        let mut app = App::new();
+        app.insert_resource(ReplayConfig::default())
+            .insert_resource(ViewerStatsPath(path.into()))
+            .add_plugins(HeadlessPlugin::default())
+            .add_plugins(SimulationPlugins);
+        Self::start(app, seed)
+    }
+
+    fn start(mut app: App, seed: u64) -> Self {
+        app.insert_resource(GameRng::new(seed));
+        let mut sim = Self { app };
+
+        // `LoadingPlugin` переводит игру в меню, когда все данные загружены.
+        let loaded = sim.run_until(LOAD_TIMEOUT, |world| *world.resource::<State<AppState>>().get() == AppState::Menu);
+        let progress = sim.app.world().resource::<LoadingProgress>();
+        assert!(loaded, "data/ was not loaded in {:?}: {:?}", LOAD_TIMEOUT, progress);
+        sim.app.world_mut().resource_mut::<NextState<AppState>>().set(AppState::InGame);
+        sim.step(1);
+        sim
+    }
+
+    /// Прогоняет `frames` кадров, каждый — ровно один шаг времени.
+    pub fn step(&mut self, frames: u32) {
+        for _ in 0..frames {
+            self.app.update();
+        }
+    }
+
+    /// Крутит кадры, пока не выполнится условие; ассеты грузятся в фоне,
+    /// поэтому ограничение — по реальному времени.
+    pub fn run_until(&mut self, timeout: Duration, mut done: impl FnMut(&mut World) -> bool) -> bool {
+        let started = Instant::now();
+        while started.elapsed() < timeout {
+            self.app.update();
+            if done(self.app.world_mut()) {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        false
+    }
+
+    /// Как [`run_until`](Self::run_until), но без пауз и с лимитом по кадрам — для прогонов на максимальной скорости.
+    pub fn step_until(&mut self, max_frames: u32, mut done: impl FnMut(&mut World) -> bool) -> bool {
+        for _ in 0..max_frames {
+            self.app.update();
+            if done(self.app.world_mut()) {
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn send<E: Event>(&mut self, event: E) {
+        self.app.world_mut().send_event(event);
+    }
+
+    /// Применяет действие тем же путём, что команды зрителей: оно попадает и в реплей.
+    pub fn apply(&mut self, action: &GameAction) -> Result<(), String> {
+        let mut state = SystemState::<GameActions>::new(self.app.world_mut());
+        let result = state.get_mut(self.app.world_mut()).apply(action);
+        state.apply(self.app.world_mut());
+        result
+    }
+
+    pub fn start_wave(&mut self) {
+        self.send(StartWaveEvent);
+    }
+
+    pub fn place_tower(&mut self, id: &str, pos: Vec3) {
+        self.send(PlaceTowerEvent { id: id.to_string(), pos, owner: None });
+    }
+
+    /// Останавливает фоновый спавн: дальше цели приходят только с волнами и по командам.
+    pub fn pause_ambient_spawn(&mut self) {
+        self.app.world_mut().resource_mut::<AmbientSpawn>().timer.pause();
+    }
+
+    /// Убирает башни, расставленные при старте.
+    pub fn clear_towers(&mut self) {
+        let world = self.app.world_mut();
+        let towers: Vec<Entity> = world.query_filtered::<Entity, With<Tower>>().iter(world).collect();
+        for tower in towers {
+            world.despawn(tower);
+        }
+    }
+
+    pub fn save_game(&mut self) -> SaveGame {
+        let mut state = SystemState::<GameSnapshot>::new(self.app.world_mut());
+        state.get(self.app.world()).capture()
+    }
+
+    /// Заменяет текущий матч сохранённым, не сдвигая симуляцию ни на шаг.
+    pub fn load_game(&mut self, save: SaveGame) {
+        self.app.insert_resource(PendingLoad(save));
+        self.app.world_mut().run_schedule(PreUpdate);
+    }
+
+    pub fn counters(&self) -> SimCounters {
+        *self.app.world().resource::<SimCounters>()
+    }
+}
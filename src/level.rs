@@ -0,0 +1,437 @@
+use crate::AppState;
+use crate::StartupStage::Load;
+use crate::cli::arg_value;
+use crate::data_format::{DataAssetPlugin, data_files_in};
+use crate::data_load::{DataErrors, assets_dir};
+use crate::economy::{Gold, Lives, STARTING_GOLD, STARTING_LIVES};
+use crate::loading::LoadingTracker;
+use crate::localization::{Locale, LocalizedText};
+use crate::mods::ModPacks;
+use crate::save_game::{MatchEntities, PendingLoad};
+use crate::target::AmbientSpawn;
+use crate::tower::{TowerDB, TowerFreeze, spawn_tower_of};
+use crate::wave::{WAVES_JSON, WaveState};
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::path::Path;
+
+/// Уровни из `data/levels/<id>.json` (или `.ron`, `.toml`): земля, свет, камера, пути целей
+/// от точек спавна до цели, зоны строительства, стартовые башни, золото, жизни и файл волн.
+///
+/// Уровень выбирается флагом `--level <id>` или кнопкой в меню, по умолчанию — [`DEFAULT_LEVEL`].
+/// Смена уровня начинает матч заново; правка файла уровня на ходу меняет мир, но не матч.
+/// Паки добавляют свои `data/levels/*`, уровень пака с тем же `id` заменяет базовый целиком.
+/// Саму сцену строит [`crate::world::WorldPlugin`].
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        let mut levels = Levels::discover(&assets_dir(), app.world().get_resource::<ModPacks>());
+        let wanted = arg_value("--level").unwrap_or_else(|| DEFAULT_LEVEL.to_string());
+        if !levels.select(&wanted) {
+            if let Some(first) = levels.files.first() {
+                warn!("Level '{}' is not found, using '{}'", wanted, first.id);
+                levels.selected = first.id.clone();
+            } else {
+                warn!("No levels in {}, using the built-in one", LEVELS_DIR);
+            }
+        }
+        app.add_plugins(DataAssetPlugin::<LevelDef>::default())
+            .insert_resource(levels)
+            .init_resource::<MatchLevel>()
+            .init_resource::<DataErrors>()
+            .add_systems(Startup, load_levels.in_set(Load))
+            .add_systems(
+                Update,
+                apply_level
+                    .in_set(Load)
+                    .run_if(on_event::<AssetEvent<LevelDef>>.or(resource_changed::<Levels>)),
+            )
+            .add_systems(
+                Update,
+                start_match
+                    .run_if(resource_exists::<ActiveLevel>)
+                    .run_if(resource_exists::<TowerDB>)
+                    .run_if(not(resource_exists::<PendingLoad>))
+                    .run_if(not(in_state(AppState::Loading))),
+            )
+            // До загрузки сохранения: матч восстанавливается на своём уровне.
+            .add_systems(PreUpdate, select_saved_level.run_if(resource_exists::<PendingLoad>));
+    }
+}
+
+pub const LEVELS_DIR: &str = "data/levels";
+pub const DEFAULT_LEVEL: &str = "meadow";
+
+/// Описание уровня. Все поля необязательны, по умолчанию — встроенный луг 50×50 с одним
+/// прямым путём.
+#[derive(Debug, Clone, Deserialize, Asset, TypePath)]
+#[serde(default, deny_unknown_fields)]
+pub struct LevelDef {
+    pub name: LocalizedText,
+    pub terrain: TerrainDef,
+    pub lights: Vec<LightDef>,
+    pub ambient: Option<AmbientDef>,
+    pub camera: Option<CameraDef>,
+    /// Пути целей; появившаяся цель выбирает один из них случайно.
+    pub paths: Vec<PathDef>,
+    /// Дошедшая сюда цель отнимает жизнь.
+    pub goal: [f32; 3],
+    /// Прямоугольники на земле, где можно строить; пустой список — строить можно везде.
+    pub build_zones: Vec<BuildZone>,
+    /// Башни, которые стоят в начале матча.
+    pub towers: Vec<StartingTower>,
+    pub gold: u32,
+    pub lives: u32,
+    /// Расписание волн уровня, путь внутри `assets`.
+    pub waves: String,
+}
+
+impl Default for LevelDef {
+    fn default() -> Self {
+        Self {
+            name: LocalizedText::new(),
+            terrain: TerrainDef::default(),
+            lights: vec![LightDef::Point { pos: [5.0, 5.0, 5.0], intensity: None, range: None, shadows: true }],
+            ambient: None,
+            camera: None,
+            paths: vec![PathDef { spawn: [-2.0, 0.0, 2.0], waypoints: Vec::new() }],
+            goal: [20.0, 0.0, 2.0],
+            build_zones: Vec::new(),
+            towers: vec![
+                StartingTower { id: "basic".to_string(), pos: [0.0, 0.0, 0.0] },
+                StartingTower { id: "sniper".to_string(), pos: [3.0, 0.0, 0.0] },
+            ],
+            gold: STARTING_GOLD,
+            lives: STARTING_LIVES,
+            waves: WAVES_JSON.to_string(),
+        }
+    }
+}
+
+/// Земля: модель из glb или плоскость заданного размера и цвета.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum TerrainDef {
+    Scene(String),
+    Ground { size: [f32; 2], color: [f32; 3] },
+}
+
+impl Default for TerrainDef {
+    fn default() -> Self {
+        Self::Ground { size: [50.0, 50.0], color: [0.3, 0.5, 0.3] }
+    }
+}
+
+/// Источник света; без `intensity`/`illuminance` и `range` — значения Bevy по умолчанию.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum LightDef {
+    Point {
+        pos: [f32; 3],
+        #[serde(default)]
+        intensity: Option<f32>,
+        #[serde(default)]
+        range: Option<f32>,
+        #[serde(default)]
+        shadows: bool,
+    },
+    Directional {
+        /// Куда светит.
+        direction: [f32; 3],
+        #[serde(default)]
+        illuminance: Option<f32>,
+        #[serde(default)]
+        shadows: bool,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AmbientDef {
+    pub color: [f32; 3],
+    pub brightness: f32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CameraDef {
+    pub pos: [f32; 3],
+    pub look_at: [f32; 3],
+}
+
+/// Путь цели: точка спавна, затем промежуточные точки по порядку, затем цель уровня.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PathDef {
+    pub spawn: [f32; 3],
+    #[serde(default)]
+    pub waypoints: Vec<[f32; 3]>,
+}
+
+/// Прямоугольник на земле по осям x и z.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BuildZone {
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+}
+
+impl BuildZone {
+    pub fn contains(&self, pos: Vec3) -> bool {
+        (self.min[0]..=self.max[0]).contains(&pos.x) && (self.min[1]..=self.max[1]).contains(&pos.z)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StartingTower {
+    /// Id из `data/towers.json`.
+    pub id: String,
+    pub pos: [f32; 3],
+}
+
+impl LevelDef {
+    pub fn can_build(&self, pos: Vec3) -> bool {
+        self.build_zones.is_empty() || self.build_zones.iter().any(|z| z.contains(pos))
+    }
+
+    /// Маршруты целей: точка спавна, промежуточные точки и цель.
+    pub fn routes(&self) -> Vec<Vec<Vec3>> {
+        self.paths
+            .iter()
+            .map(|p| {
+                let points = std::iter::once(p.spawn).chain(p.waypoints.iter().copied());
+                points.chain([self.goal]).map(Vec3::from_array).collect()
+            })
+            .collect()
+    }
+
+    /// Ошибки с файлом и полем; пустой список — уровень годен. Id стартовых башен не
+    /// проверяются: `TowerDB` может быть ещё не собрана.
+    pub fn validate(&self, file: &str) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut error = |msg: String| errors.push(format!("{}: {}", file, msg));
+        if self.paths.is_empty() {
+            error("field `paths` must not be empty".to_string());
+        }
+        let points = self.paths.iter().flat_map(|p| std::iter::once(&p.spawn).chain(&p.waypoints));
+        if !points.chain([&self.goal]).all(|p| p.iter().all(|c| c.is_finite())) {
+            error("path points and `goal` must be finite".to_string());
+        }
+        for (i, zone) in self.build_zones.iter().enumerate() {
+            if !(zone.min[0] <= zone.max[0] && zone.min[1] <= zone.max[1]) {
+                error(format!("build_zones[{}]: `min` {:?} must not exceed `max` {:?}", i, zone.min, zone.max));
+            }
+        }
+        for t in &self.towers {
+            if !self.can_build(Vec3::from_array(t.pos)) {
+                error(format!("towers: '{}' at {:?} is outside `build_zones`", t.id, t.pos));
+            }
+        }
+        if self.lives == 0 {
+            error("field `lives` must be positive".to_string());
+        }
+        if self.waves.is_empty() {
+            error("field `waves` must not be empty".to_string());
+        }
+        if let TerrainDef::Ground { size, .. } = &self.terrain
+            && !size.iter().all(|s| s.is_finite() && *s > 0.0)
+        {
+            error(format!("terrain: ground size must be positive, got {:?}", size));
+        }
+        errors
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LevelFile {
+    pub id: String,
+    /// Путь относительно `assets`.
+    pub path: String,
+    pub handle: Handle<LevelDef>,
+}
+
+impl LevelFile {
+    /// Название на языке интерфейса; пока файл не загружен или без названия — `id`.
+    pub fn display_name<'a>(&'a self, defs: &'a Assets<LevelDef>, locale: &Locale) -> &'a str {
+        defs.get(&self.handle).and_then(|def| locale.text(&def.name)).unwrap_or(&self.id)
+    }
+}
+
+/// Все найденные уровни по `id` и выбранный.
+#[derive(Resource, Debug, Default)]
+pub struct Levels {
+    pub files: Vec<LevelFile>,
+    pub selected: String,
+}
+
+impl Levels {
+    /// Уровни из `assets/data/levels` и из паков; хэндлы пустые до [`load_levels`].
+    pub fn discover(assets: &Path, packs: Option<&ModPacks>) -> Self {
+        let dirs = std::iter::once(LEVELS_DIR.to_string())
+            .chain(packs.into_iter().flat_map(|p| p.packs.iter().map(|p| format!("{}/{}", p.dir, LEVELS_DIR))));
+        let mut files: Vec<LevelFile> = Vec::new();
+        for dir in dirs {
+            for (id, path) in data_files_in(assets, &dir) {
+                let file = LevelFile { id, path, handle: Handle::default() };
+                match files.iter_mut().find(|f| f.id == file.id) {
+                    Some(previous) => {
+                        info!("{}: level '{}' overrides {}", file.path, file.id, previous.path);
+                        *previous = file;
+                    }
+                    None => files.push(file),
+                }
+            }
+        }
+        files.sort_by(|a, b| a.id.cmp(&b.id));
+        Self { files, selected: String::new() }
+    }
+
+    /// `false`, если уровня с таким `id` нет; тогда выбор не меняется.
+    pub fn select(&mut self, id: &str) -> bool {
+        if !self.files.iter().any(|f| f.id == id) {
+            return false;
+        }
+        self.selected = id.to_string();
+        true
+    }
+
+    /// Следующий уровень по кругу — для кнопки в меню.
+    pub fn select_next(&mut self) {
+        let Some(i) = self.files.iter().position(|f| f.id == self.selected) else { return; };
+        self.selected = self.files[(i + 1) % self.files.len()].id.clone();
+    }
+
+    pub fn selected_file(&self) -> Option<&LevelFile> {
+        self.files.iter().find(|f| f.id == self.selected)
+    }
+}
+
+/// Уровень, по которому сейчас строится мир.
+#[derive(Resource, Debug, Clone)]
+pub struct ActiveLevel {
+    pub id: String,
+    pub def: LevelDef,
+    /// См. [`LevelDef::routes`].
+    pub routes: Vec<Vec<Vec3>>,
+}
+
+impl ActiveLevel {
+    pub fn new(id: impl Into<String>, def: LevelDef) -> Self {
+        Self { id: id.into(), routes: def.routes(), def }
+    }
+
+    pub fn can_build(&self, pos: Vec3) -> bool {
+        self.def.can_build(pos)
+    }
+}
+
+/// Уровень, под который собран текущий матч; `None` — матча ещё не было.
+#[derive(Resource, Debug, Default)]
+pub struct MatchLevel(pub Option<String>);
+
+/// Грузит все уровни, чтобы меню знало их названия; ждать загрузки стоит только выбранного.
+fn load_levels(mut levels: ResMut<Levels>, server: Res<AssetServer>, mut tracker: ResMut<LoadingTracker>) {
+    let selected = levels.selected.clone();
+    for file in &mut levels.files {
+        file.handle = server.load(file.path.clone());
+        if file.id == selected {
+            tracker.track(file.path.clone(), file.handle.clone());
+        }
+    }
+}
+
+fn apply_level(
+    mut commands: Commands,
+    levels: Res<Levels>,
+    defs: Res<Assets<LevelDef>>,
+    active: Option<Res<ActiveLevel>>,
+    mut events: EventReader<AssetEvent<LevelDef>>,
+    mut data_errors: ResMut<DataErrors>,
+) {
+    let Some(file) = levels.selected_file() else {
+        // Уровней нет совсем — играем на встроенном.
+        if active.is_none() {
+            commands.insert_resource(ActiveLevel::new(DEFAULT_LEVEL, LevelDef::default()));
+        }
+        return;
+    };
+    let modified = events.read().any(|e| e.is_loaded_with_dependencies(&file.handle) || e.is_modified(&file.handle));
+    let switched = active.as_ref().is_none_or(|a| a.id != file.id);
+    if !(modified || switched) {
+        return;
+    }
+    // Ещё грузится; применим по событию загрузки.
+    let Some(def) = defs.get(&file.handle) else { return; };
+
+    data_errors.0.retain(|e| !e.starts_with(&file.path));
+    let errors = def.validate(&file.path);
+    if !errors.is_empty() {
+        for e in errors {
+            error!("{}", e);
+            data_errors.0.push(e);
+        }
+        if active.is_some() {
+            warn!("{} has errors, keeping the current level", file.path);
+            return;
+        }
+        warn!("{} has errors, using the built-in level", file.path);
+        commands.insert_resource(ActiveLevel::new(file.id.clone(), LevelDef::default()));
+        return;
+    }
+    info!("Level '{}' applied from {}", file.id, file.path);
+    commands.insert_resource(ActiveLevel::new(file.id.clone(), def.clone()));
+}
+
+/// Новый уровень — новый матч: убирает башни и цели, ставит стартовые башни,
+/// золото, жизни и волны уровня. Оплаченные зрителями башни остаются в очереди.
+fn start_match(
+    mut commands: Commands,
+    level: Res<ActiveLevel>,
+    db: Res<TowerDB>,
+    mut match_level: ResMut<MatchLevel>,
+    existing: Query<Entity, MatchEntities>,
+) {
+    if match_level.0.as_deref() == Some(level.id.as_str()) {
+        return;
+    }
+    for e in &existing {
+        commands.entity(e).despawn();
+    }
+    for t in &level.def.towers {
+        match db.defs.get(&t.id) {
+            Some(def) => spawn_tower_of(&mut commands, def, Vec3::from_array(t.pos), None),
+            None => warn!("Level '{}': starting tower '{}' is missing from TowerDB", level.id, t.id),
+        }
+    }
+    commands.insert_resource(Gold(level.def.gold));
+    commands.insert_resource(Lives(level.def.lives));
+    commands.insert_resource(WaveState::default());
+    commands.insert_resource(TowerFreeze::default());
+    commands.insert_resource(AmbientSpawn::default());
+    info!("Match started on level '{}'", level.id);
+    match_level.0 = Some(level.id.clone());
+}
+
+/// Сохранение с другого уровня сначала переключает уровень.
+fn select_saved_level(pending: Res<PendingLoad>, mut levels: ResMut<Levels>) {
+    let Some(id) = &pending.0.level else { return; };
+    if levels.selected == *id {
+        return;
+    }
+    if levels.select(id) {
+        info!("Save is from level '{}', switching", id);
+    } else {
+        warn!("Save is from unknown level '{}', loading it on '{}'", id, levels.selected);
+    }
+}
+
+/// Загрузка сохранения ждёт, пока применится его уровень (или уровня в сохранении нет).
+pub fn saved_level_ready(pending: Option<Res<PendingLoad>>, levels: Res<Levels>, active: Option<Res<ActiveLevel>>) -> bool {
+    let (Some(pending), Some(active)) = (pending, active) else { return false; };
+    match &pending.0.level {
+        Some(id) if levels.files.iter().any(|f| f.id == *id) => active.id == *id,
+        _ => true,
+    }
+}
@@ -0,0 +1,191 @@
+//! Stream TD: tower defense, которым управляет чат стрима.
+//!
+//! [`GamePlugin`] собирает всю игру; для тестов, утилит и headless-сервера можно
+//! подключать группы по отдельности: [`SimulationPlugins`] — правила игры без окна и звука,
+//! [`StreamPlugins`] — источники событий стрима, [`PresentationPlugins`] — всё, что рисует и
+//! читает ввод игрока. Окно, рендер, egui и инспектор добавляет сам бинарник;
+//! без них игру запускает [`headless::HeadlessPlugin`].
+
+pub mod tower;
+pub mod world;
+pub mod projectile;
+pub mod target;
+pub mod camera;
+pub mod pixel_plugin;
+pub mod blood;
+pub mod main_menu;
+pub mod data_load;
+pub mod data_format;
+pub mod egui_setup;
+pub mod input_system;
+pub mod tower_build;
+pub mod economy;
+pub mod wave;
+pub mod command_server;
+pub mod game_action;
+pub mod stream_event;
+pub mod twitch_irc;
+pub mod cli;
+pub mod stream_script;
+pub mod viewer_stats;
+pub mod webhook;
+pub mod moderation;
+pub mod command_queue;
+pub mod headless;
+pub mod rng;
+pub mod fixed_step;
+pub mod replay;
+pub mod save_game;
+pub mod loading;
+pub mod mods;
+pub mod localization;
+pub mod level;
+
+use bevy::app::PluginGroupBuilder;
+use bevy::prelude::*;
+use crate::StartupStage::{Build, Load, Processing};
+use crate::blood::BloodPlugin;
+use crate::camera::CustomCameraPlugin;
+use crate::command_queue::{CommandQueuePanelPlugin, CommandQueuePlugin};
+use crate::command_server::CommandServerPlugin;
+use crate::data_load::{DataErrorsPanelPlugin, DataLoadPlugin};
+use crate::economy::EconomyPlugin;
+use crate::fixed_step::FixedStepPlugin;
+use crate::game_action::GameActionPlugin;
+use crate::egui_setup::EguiConfigurePlugin;
+use crate::input_system::PlayerInputPlugin;
+use crate::level::LevelPlugin;
+use crate::loading::{LoadingPlugin, LoadingScreenPlugin};
+use crate::localization::LocalizationPlugin;
+use crate::main_menu::MainMenuPlugin;
+use crate::moderation::{ModerationPanelPlugin, ModerationPlugin};
+use crate::mods::ModsPlugin;
+use crate::projectile::{ProjectilePlugin, ProjectileViewPlugin};
+use crate::replay::ReplayPlugin;
+use crate::rng::RngPlugin;
+use crate::save_game::{QuicksavePlugin, SaveGamePlugin};
+use crate::stream_event::StreamEventPlugin;
+use crate::stream_script::StreamScriptPlugin;
+use crate::target::{TargetPlugin, TargetViewPlugin};
+use crate::tower::{TowerPlugin, TowerViewPlugin};
+use crate::tower_build::TowerBuildPlugin;
+use crate::twitch_irc::TwitchIrcPlugin;
+use crate::viewer_stats::{LeaderboardPlugin, ViewerStatsPlugin};
+use crate::wave::WavePlugin;
+use crate::webhook::WebhookPlugin;
+use crate::world::WorldPlugin;
+
+/// Вся игра целиком. Ожидает, что `DefaultPlugins`, `MeshPickingPlugin` и `EguiPlugin`
+/// уже добавлены.
+pub struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((SimulationPlugins, StreamPlugins, PresentationPlugins));
+    }
+}
+
+/// Правила игры: данные, уровни, башни, цели, снаряды, волны, экономика и реакции на стрим.
+/// Заводит стейты и наборы систем, так что годится и без [`GamePlugin`].
+pub struct SimulationPlugins;
+
+impl PluginGroup for SimulationPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(CoreStatesPlugin)
+            .add(LoadingPlugin)
+            .add(RngPlugin)
+            .add(FixedStepPlugin)
+            .add(GameActionPlugin)
+            .add(ModsPlugin)
+            .add(DataLoadPlugin)
+            .add(LevelPlugin)
+            .add(TowerPlugin)
+            .add(TargetPlugin)
+            .add(ProjectilePlugin)
+            .add(EconomyPlugin)
+            .add(WavePlugin)
+            .add(StreamEventPlugin)
+            .add(ModerationPlugin)
+            .add(CommandQueuePlugin)
+            .add(ViewerStatsPlugin)
+            .add(ReplayPlugin)
+            .add(SaveGamePlugin)
+    }
+}
+
+/// Внешние источники событий стрима: TCP-команды, Twitch IRC, вебхуки, сценарии.
+pub struct StreamPlugins;
+
+impl PluginGroup for StreamPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(CommandServerPlugin)
+            .add(TwitchIrcPlugin)
+            .add(WebhookPlugin)
+            .add(StreamScriptPlugin)
+    }
+}
+
+/// Переводы, сцена, камера, модели и звуки, эффекты, меню, egui-панели и ввод игрока.
+pub struct PresentationPlugins;
+
+impl PluginGroup for PresentationPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(LocalizationPlugin)
+            .add(LoadingScreenPlugin)
+            .add(WorldPlugin)
+            .add(CustomCameraPlugin)
+            .add(TowerViewPlugin)
+            .add(TargetViewPlugin)
+            .add(ProjectileViewPlugin)
+            //.add(PixelPlugin)
+            .add(BloodPlugin)
+            .add(MainMenuPlugin)
+            .add(EguiConfigurePlugin)
+            .add(PlayerInputPlugin)
+            .add(TowerBuildPlugin)
+            .add(DataErrorsPanelPlugin)
+            .add(ModerationPanelPlugin)
+            .add(CommandQueuePanelPlugin)
+            .add(LeaderboardPlugin)
+            .add(QuicksavePlugin)
+    }
+}
+
+/// Стейты и порядок этапов загрузки, на которые опираются остальные плагины.
+struct CoreStatesPlugin;
+
+impl Plugin for CoreStatesPlugin {
+    fn build(&self, app: &mut App) {
+        app.configure_sets(Startup, (Load, Processing, Build).chain())
+            .configure_sets(Update, (Load, Processing, Build).chain())
+            .init_state::<AppState>()
+            .init_state::<PlayerState>();
+    }
+}
+
+#[derive(States, Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub enum AppState {
+    /// Ждём ассеты и данные, см. [`loading::LoadingPlugin`].
+    #[default]
+    Loading,
+    Menu,
+    InGame,
+    Paused,
+}
+#[derive(States, Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub enum PlayerState {
+    #[default]
+    Build,
+    None
+}
+
+
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+pub enum StartupStage {
+    Load,
+    Processing,
+    Build,
+}
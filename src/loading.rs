@@ -0,0 +1,183 @@
+use crate::AppState;
+use crate::StartupStage::Build;
+use crate::localization::Locale;
+use crate::level::ActiveLevel;
+use crate::target::TargetCatalog;
+use crate::tower::TowerDB;
+use bevy::asset::RecursiveDependencyLoadState;
+use bevy::prelude::*;
+
+/// Экран загрузки: игра стартует в [`AppState::Loading`] и переходит в меню, только
+/// когда загружены все ассеты из [`LoadingTracker`], собраны `TowerDB` и каталог врагов и применён уровень.
+///
+/// Плагины регистрируют свои хэндлы через [`LoadingTracker::track`]. Если что-то не
+/// загрузилось, игра остаётся на экране загрузки и показывает ошибки вместо паники.
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LoadingTracker>()
+            .init_resource::<LoadingProgress>()
+            .add_systems(Update, check_loading.in_set(Build).run_if(in_state(AppState::Loading)));
+    }
+}
+
+/// Полоса прогресса и список ошибок загрузки.
+pub struct LoadingScreenPlugin;
+
+impl Plugin for LoadingScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Loading), spawn_loading_screen)
+            .add_systems(
+                Update,
+                update_loading_screen
+                    .run_if(in_state(AppState::Loading))
+                    .run_if(resource_changed::<LoadingProgress>),
+            )
+            .add_systems(OnExit(AppState::Loading), despawn_loading_screen);
+    }
+}
+
+/// Хэндлы, без которых нельзя начинать игру.
+#[derive(Resource, Default)]
+pub struct LoadingTracker {
+    handles: Vec<(String, UntypedHandle)>,
+}
+
+impl LoadingTracker {
+    /// `label` — путь или имя ассета для сообщения об ошибке.
+    pub fn track(&mut self, label: impl Into<String>, handle: impl Into<UntypedHandle>) {
+        self.handles.push((label.into(), handle.into()));
+    }
+}
+
+#[derive(Resource, Debug, Clone, Default)]
+pub struct LoadingProgress {
+    pub loaded: usize,
+    pub total: usize,
+    pub errors: Vec<String>,
+}
+
+impl LoadingProgress {
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 { 1.0 } else { self.loaded as f32 / self.total as f32 }
+    }
+}
+
+fn check_loading(
+    tracker: Res<LoadingTracker>,
+    server: Res<AssetServer>,
+    (db, catalog, level): (Option<Res<TowerDB>>, Res<TargetCatalog>, Option<Res<ActiveLevel>>),
+    mut progress: ResMut<LoadingProgress>,
+    mut state: ResMut<NextState<AppState>>,
+) {
+    // После ошибки остаёмся на экране с её описанием.
+    if !progress.errors.is_empty() {
+        return;
+    }
+
+    let mut loaded = 0;
+    let mut errors = Vec::new();
+    for (label, handle) in &tracker.handles {
+        match server.get_recursive_dependency_load_state(handle.id()) {
+            // Хэндл не от сервера (добавлен в `Assets` напрямую) — ждать нечего.
+            None | Some(RecursiveDependencyLoadState::Loaded) => loaded += 1,
+            Some(RecursiveDependencyLoadState::Failed(e)) => errors.push(format!("{}: {}", label, e)),
+            Some(_) => {}
+        }
+    }
+
+    if progress.loaded != loaded || progress.total != tracker.handles.len() {
+        progress.loaded = loaded;
+        progress.total = tracker.handles.len();
+    }
+    if !errors.is_empty() {
+        for e in &errors {
+            error!("Loading failed: {}", e);
+        }
+        progress.errors = errors;
+        return;
+    }
+    if loaded == tracker.handles.len() && db.is_some() && !catalog.is_empty() && level.is_some() {
+        info!("All {} assets loaded", loaded);
+        state.set(AppState::Menu);
+    }
+}
+
+const BAR_WIDTH: f32 = 400.0;
+const LOADING_FONT: &str = "fonts/font_regular.ttf";
+const ERROR_COLOR: Color = Color::srgb(0.9, 0.3, 0.25);
+
+#[derive(Component)]
+struct LoadingScreenRoot;
+
+#[derive(Component)]
+struct LoadingText;
+
+#[derive(Component)]
+struct LoadingBar;
+
+fn spawn_loading_screen(mut commands: Commands, server: Res<AssetServer>) {
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            row_gap: Val::Px(16.0),
+            ..default()
+        },
+        BackgroundColor(Color::BLACK),
+        LoadingScreenRoot,
+        children![
+            (
+                Text::default(),
+                // У встроенного шрифта Bevy нет кириллицы.
+                TextFont { font_size: 28.0, font: server.load(LOADING_FONT), ..default() },
+                LoadingText,
+            ),
+            (
+                Node {
+                    width: Val::Px(BAR_WIDTH),
+                    height: Val::Px(12.0),
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(0.2, 0.2, 0.2)),
+                children![(
+                    Node {
+                        width: Val::Px(0.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.95, 0.8, 0.35)),
+                    LoadingBar,
+                )],
+            ),
+        ],
+    ));
+}
+
+fn update_loading_screen(
+    progress: Res<LoadingProgress>,
+    locale: Res<Locale>,
+    mut text: Query<(&mut Text, &mut TextColor), With<LoadingText>>,
+    mut bar: Query<&mut Node, With<LoadingBar>>,
+) {
+    let Ok((mut text, mut color)) = text.single_mut() else { return; };
+    if progress.errors.is_empty() {
+        text.0 = locale.fmt("loading.progress", &[("loaded", &progress.loaded), ("total", &progress.total)]);
+    } else {
+        text.0 = locale.fmt("loading.failed", &[("errors", &progress.errors.join("\n"))]);
+        color.0 = ERROR_COLOR;
+    }
+    if let Ok(mut bar) = bar.single_mut() {
+        bar.width = Val::Px(BAR_WIDTH * progress.fraction());
+    }
+}
+
+fn despawn_loading_screen(mut commands: Commands, q: Query<Entity, With<LoadingScreenRoot>>) {
+    for e in &q {
+        commands.entity(e).despawn();
+    }
+}
@@ -0,0 +1,210 @@
+use crate::cli::arg_value;
+use crate::data_format::{data_files_in, parse_data};
+use crate::data_load::assets_dir;
+use crate::mods::ModPacks;
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::Path;
+
+/// Переводы интерфейса: таблицы строк `assets/locale/<язык>.json` (или `.ron`, `.toml`)
+/// вида `{"language": "English", "strings": {"menu.start": "Start Game"}}`.
+///
+/// Язык — `--lang ru`, по умолчанию [`FALLBACK_LANGUAGE`]; в меню его можно переключить.
+/// Ключа нет в таблице языка — берётся из английской, нет и там — показывается сам ключ.
+/// Подстановки пишутся как `{gold}`, см. [`Locale::fmt`]. Паки могут дополнять таблицы
+/// своими `locale/<язык>.json` и добавлять новые языки. Таблицы читаются при сборке приложения.
+pub struct LocalizationPlugin;
+
+impl Plugin for LocalizationPlugin {
+    fn build(&self, app: &mut App) {
+        let (tables, errors) = load_tables(&assets_dir(), app.world().get_resource::<ModPacks>());
+        for e in errors.iter().chain(&check_tables(&tables)) {
+            warn!("{}", e);
+        }
+        let mut locale = Locale::from_tables(tables);
+        if let Some(lang) = arg_value("--lang")
+            && !locale.set_language(&lang)
+        {
+            warn!("Unknown language '{}', using '{}'", lang, FALLBACK_LANGUAGE);
+        }
+        info!("Language: {}", locale.language);
+        app.insert_resource(locale).add_systems(Update, update_localized_labels);
+    }
+}
+
+pub const LOCALE_DIR: &str = "locale";
+/// Язык, в котором должны быть все ключи; им закрываются пробелы в остальных.
+pub const FALLBACK_LANGUAGE: &str = "en";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LocaleFile {
+    /// Название языка для меню: «English», «Русский».
+    #[serde(default)]
+    pub language: String,
+    #[serde(default)]
+    pub strings: BTreeMap<String, String>,
+}
+
+/// Текст из файла данных на нескольких языках: `{"en": "Cannon", "ru": "Пушка"}`.
+pub type LocalizedText = BTreeMap<String, String>;
+
+#[derive(Resource, Debug, Default)]
+pub struct Locale {
+    language: String,
+    tables: BTreeMap<String, LocaleFile>,
+}
+
+impl Locale {
+    /// Таблицы из [`load_tables`], язык — [`FALLBACK_LANGUAGE`].
+    pub fn from_tables(tables: BTreeMap<String, LocaleFile>) -> Self {
+        Self { language: FALLBACK_LANGUAGE.to_string(), tables }
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Название текущего языка из его таблицы, без названия — код.
+    pub fn language_name(&self) -> &str {
+        match self.tables.get(&self.language) {
+            Some(t) if !t.language.is_empty() => &t.language,
+            _ => &self.language,
+        }
+    }
+
+    /// `false`, если таблицы для `code` нет; тогда язык не меняется.
+    pub fn set_language(&mut self, code: &str) -> bool {
+        if !self.tables.contains_key(code) {
+            return false;
+        }
+        self.language = code.to_string();
+        true
+    }
+
+    /// Следующий язык по кругу — для кнопки в меню.
+    pub fn next_language(&mut self) {
+        let codes: Vec<_> = self.tables.keys().cloned().collect();
+        let Some(i) = codes.iter().position(|c| *c == self.language) else { return; };
+        self.language = codes[(i + 1) % codes.len()].clone();
+    }
+
+    /// Строка по ключу на текущем языке или на [`FALLBACK_LANGUAGE`].
+    pub fn get(&self, key: &str) -> Option<&str> {
+        [self.language.as_str(), FALLBACK_LANGUAGE]
+            .iter()
+            .find_map(|lang| self.tables.get(*lang)?.strings.get(key))
+            .map(String::as_str)
+    }
+
+    /// Как [`Locale::get`], но без перевода возвращает сам ключ.
+    pub fn t<'a>(&'a self, key: &'a str) -> &'a str {
+        self.get(key).unwrap_or(key)
+    }
+
+    /// Строка по ключу с подстановками: `fmt("hud.gold", &[("gold", &gold.0)])` для `"Gold: {gold}"`.
+    pub fn fmt(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        args.iter()
+            .fold(self.t(key).to_string(), |text, (name, value)| text.replace(&format!("{{{}}}", name), &value.to_string()))
+    }
+
+    /// Перевод текста из файла данных; пустой текст — `None`, пусть вызывающий покажет id.
+    pub fn text<'a>(&self, text: &'a LocalizedText) -> Option<&'a str> {
+        [self.language.as_str(), FALLBACK_LANGUAGE]
+            .iter()
+            .find_map(|lang| text.get(*lang))
+            .or_else(|| text.values().next())
+            .map(String::as_str)
+    }
+}
+
+/// Текст UI-узла, который берётся из таблицы по ключу и меняется вместе с языком.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct LocalizedLabel(pub &'static str);
+
+fn update_localized_labels(locale: Res<Locale>, mut labels: Query<(Ref<LocalizedLabel>, &mut Text)>) {
+    for (label, mut text) in &mut labels {
+        if locale.is_changed() || label.is_added() {
+            text.0 = locale.t(label.0).to_string();
+        }
+    }
+}
+
+/// Файлы таблиц по языкам, в порядке применения: сначала из `assets/locale`, затем из паков.
+pub fn locale_files(assets: &Path, packs: Option<&ModPacks>) -> BTreeMap<String, Vec<String>> {
+    let dirs = std::iter::once(LOCALE_DIR.to_string())
+        .chain(packs.into_iter().flat_map(|p| p.packs.iter().map(|p| format!("{}/{}", p.dir, LOCALE_DIR))));
+    let mut files: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for dir in dirs {
+        for (lang, file) in data_files_in(assets, &dir) {
+            files.entry(lang).or_default().push(file);
+        }
+    }
+    files
+}
+
+/// Таблицы всех языков со строками из паков поверх базовых; нечитаемые файлы пропускаются с ошибкой.
+pub fn load_tables(assets: &Path, packs: Option<&ModPacks>) -> (BTreeMap<String, LocaleFile>, Vec<String>) {
+    let mut tables = BTreeMap::new();
+    let mut errors = Vec::new();
+    for (lang, files) in locale_files(assets, packs) {
+        let mut table = LocaleFile::default();
+        for file in files {
+            let parsed = std::fs::read_to_string(assets.join(&file))
+                .map_err(anyhow::Error::from)
+                .and_then(|text| parse_data::<LocaleFile>(Path::new(&file), &text));
+            match parsed {
+                Ok(layer) => {
+                    if !layer.language.is_empty() {
+                        table.language = layer.language;
+                    }
+                    table.strings.extend(layer.strings);
+                }
+                Err(e) => errors.push(format!("{}: {:#}, the file is skipped", file, e)),
+            }
+        }
+        tables.insert(lang, table);
+    }
+    (tables, errors)
+}
+
+/// Пробелы в переводах: ключи, которых нет в [`FALLBACK_LANGUAGE`] или в других языках,
+/// и переводы с другим набором подстановок.
+pub fn check_tables(tables: &BTreeMap<String, LocaleFile>) -> Vec<String> {
+    let Some(fallback) = tables.get(FALLBACK_LANGUAGE) else {
+        return vec![format!("{}/{}.json: no table for the fallback language", LOCALE_DIR, FALLBACK_LANGUAGE)];
+    };
+    let mut problems = Vec::new();
+    for (lang, table) in tables.iter().filter(|(lang, _)| *lang != FALLBACK_LANGUAGE) {
+        for (key, text) in &fallback.strings {
+            match table.strings.get(key) {
+                None => problems.push(format!("locale '{}': key '{}' is not translated", lang, key)),
+                Some(t) if placeholders(t) != placeholders(text) => problems.push(format!(
+                    "locale '{}': key '{}' has placeholders {:?}, expected {:?}",
+                    lang,
+                    key,
+                    placeholders(t),
+                    placeholders(text)
+                )),
+                Some(_) => {}
+            }
+        }
+        for key in table.strings.keys().filter(|k| !fallback.strings.contains_key(*k)) {
+            problems.push(format!("locale '{}': key '{}' is missing in '{}'", lang, key, FALLBACK_LANGUAGE));
+        }
+    }
+    problems
+}
+
+fn placeholders(text: &str) -> Vec<&str> {
+    let mut names: Vec<_> = text
+        .split('{')
+        .skip(1)
+        .filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+    names
+}
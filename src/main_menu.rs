@@ -1,6 +1,11 @@
 use crate::AppState;
+use crate::level::{LevelDef, Levels};
+use crate::loading::LoadingTracker;
+use crate::localization::{Locale, LocalizedLabel};
+use crate::save_game::{LoadGameEvent, SaveGameConfig};
 use bevy::app::{App, Plugin};
 use bevy::audio::Volume;
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 
 const TEXT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
@@ -10,9 +15,20 @@ pub struct MainMenuPlugin;
 #[derive(Component)]
 enum MenuButtonAction {
     Play,
+    Load,
+    Level,
+    Language,
     Quit,
 }
 
+/// Надпись на кнопке языка — название текущего языка.
+#[derive(Component)]
+struct LanguageButtonText;
+
+/// Надпись на кнопке уровня — название выбранного уровня.
+#[derive(Component)]
+struct LevelButtonText;
+
 #[derive(Component)]
 struct MainMenuRoot;
 
@@ -22,7 +38,17 @@ impl Plugin for MainMenuPlugin {
             .add_systems(OnEnter(AppState::Menu), spawn_main_menu)
             .add_systems(
                 Update,
-                (menu_action, keyboard_input, button_visuals_and_sounds),
+                (
+                    menu_action,
+                    keyboard_input,
+                    button_visuals_and_sounds,
+                    update_language_button.run_if(resource_changed::<Locale>),
+                    update_level_button.run_if(
+                        resource_changed::<Locale>
+                            .or(resource_changed::<Levels>)
+                            .or(on_event::<AssetEvent<LevelDef>>),
+                    ),
+                ),
             )
             .add_systems(OnExit(AppState::Menu), despawn_main_menu)
             .add_systems(OnEnter(AppState::Paused), pause_all_animations)
@@ -30,7 +56,7 @@ impl Plugin for MainMenuPlugin {
     }
 }
 
-fn spawn_main_menu(mut commands: Commands, assets: Res<MenuAssets>) {
+fn spawn_main_menu(mut commands: Commands, assets: Res<MenuAssets>, locale: Res<Locale>, levels: LevelNames) {
     let button_node = Node {
         width: Val::Px(200.0),
         height: Val::Px(40.0),
@@ -58,12 +84,13 @@ fn spawn_main_menu(mut commands: Commands, assets: Res<MenuAssets>) {
                 flex_direction: FlexDirection::Column,
                 align_items: AlignItems::Center,
                 width: Val::Px(450.0),
-                height: Val::Px(670.0),
+                height: Val::Px(760.0),
                 ..default()
             },
             children![
                 (
-                    Text::new("GAME MENU"),
+                    Text::default(),
+                    LocalizedLabel("menu.title"),
                     TextFont {
                         font_size: 30.0,
                         font: assets.font_reg.clone(),
@@ -82,7 +109,59 @@ fn spawn_main_menu(mut commands: Commands, assets: Res<MenuAssets>) {
                     BorderColor(Color::NONE),
                     MenuButtonAction::Play,
                     children![(
-                        Text::new("Start Game"),
+                        Text::default(),
+                        LocalizedLabel("menu.start"),
+                        TextFont {
+                            font_size: 25.0,
+                            font: assets.font_it.clone(),
+                            ..default()
+                        },
+                        TextColor(TEXT_COLOR),
+                    ),]
+                ),
+                (
+                    Button,
+                    button_node.clone(),
+                    BorderRadius::all(Val::Px(8.0)),
+                    BorderColor(Color::NONE),
+                    MenuButtonAction::Load,
+                    children![(
+                        Text::default(),
+                        LocalizedLabel("menu.load"),
+                        TextFont {
+                            font_size: 25.0,
+                            font: assets.font_it.clone(),
+                            ..default()
+                        },
+                        TextColor(TEXT_COLOR),
+                    ),]
+                ),
+                (
+                    Button,
+                    button_node.clone(),
+                    BorderRadius::all(Val::Px(8.0)),
+                    BorderColor(Color::NONE),
+                    MenuButtonAction::Level,
+                    children![(
+                        Text::new(levels.label(&locale)),
+                        LevelButtonText,
+                        TextFont {
+                            font_size: 25.0,
+                            font: assets.font_it.clone(),
+                            ..default()
+                        },
+                        TextColor(TEXT_COLOR),
+                    ),]
+                ),
+                (
+                    Button,
+                    button_node.clone(),
+                    BorderRadius::all(Val::Px(8.0)),
+                    BorderColor(Color::NONE),
+                    MenuButtonAction::Language,
+                    children![(
+                        Text::new(locale.language_name()),
+                        LanguageButtonText,
                         TextFont {
                             font_size: 25.0,
                             font: assets.font_it.clone(),
@@ -98,7 +177,8 @@ fn spawn_main_menu(mut commands: Commands, assets: Res<MenuAssets>) {
                     BorderRadius::all(Val::Px(8.0)),
                     MenuButtonAction::Quit,
                     children![(
-                        Text::new("Exit"),
+                        Text::default(),
+                        LocalizedLabel("menu.exit"),
                         TextFont {
                             font_size: 25.0,
                             font: assets.font_it.clone(),
@@ -118,6 +198,9 @@ fn menu_action(
         (Changed<Interaction>, With<Button>),
     >,
     mut menu_state: ResMut<NextState<AppState>>,
+    mut load_ev: EventWriter<LoadGameEvent>,
+    saves: Res<SaveGameConfig>,
+    (mut locale, mut levels): (ResMut<Locale>, ResMut<Levels>),
 ) {
     for (interaction, menu_button_action) in &interaction_query {
         if *interaction == Interaction::Pressed {
@@ -128,16 +211,61 @@ fn menu_action(
                 MenuButtonAction::Play => {
                     menu_state.set(AppState::InGame);
                 }
+                MenuButtonAction::Load => match saves.latest() {
+                    Some(path) => {
+                        load_ev.write(LoadGameEvent { path });
+                    }
+                    None => warn!("No saved game to load"),
+                },
+                // Новый уровень начнёт матч заново, см. `crate::level`.
+                MenuButtonAction::Level => {
+                    levels.select_next();
+                    info!("Level: {}", levels.selected);
+                }
+                MenuButtonAction::Language => {
+                    locale.next_language();
+                    info!("Language: {}", locale.language());
+                }
             }
         }
     }
 }
 
+/// Выбранный уровень и загруженные описания — для подписи кнопки.
+#[derive(SystemParam)]
+struct LevelNames<'w> {
+    levels: Res<'w, Levels>,
+    defs: Res<'w, Assets<LevelDef>>,
+}
+
+impl LevelNames<'_> {
+    fn label(&self, locale: &Locale) -> String {
+        let name = self.levels.selected_file().map_or("", |f| f.display_name(&self.defs, locale));
+        locale.fmt("menu.level", &[("level", &name)])
+    }
+}
+
+fn update_level_button(locale: Res<Locale>, levels: LevelNames, mut q: Query<&mut Text, With<LevelButtonText>>) {
+    for mut text in &mut q {
+        text.0 = levels.label(&locale);
+    }
+}
+
+fn update_language_button(locale: Res<Locale>, mut q: Query<&mut Text, With<LanguageButtonText>>) {
+    for mut text in &mut q {
+        text.0 = locale.language_name().to_string();
+    }
+}
+
 fn keyboard_input(
     keyboard: Res<ButtonInput<KeyCode>>,
     mut menu_state: ResMut<NextState<AppState>>,
     current_state: Res<State<AppState>>,
 ) {
+    if *current_state.get() == AppState::Loading {
+        return;
+    }
+
     if keyboard.just_pressed(KeyCode::Escape) {
         if *current_state.get() != AppState::Menu {
             menu_state.set(AppState::Menu);
@@ -219,12 +347,20 @@ struct MenuAssets {
 impl FromWorld for MenuAssets {
     fn from_world(world: &mut World) -> Self {
         let server = world.resource::<AssetServer>();
-        Self {
+        let assets = Self {
             font_reg: server.load("fonts/font_regular.ttf"),
             font_it: server.load("fonts/font_it.ttf"),
             bg_scroll: server.load("buttons/menu_background.png"),
             sfx_hover: server.load("buttons/button_hover.ogg"),
             sfx_click: server.load("buttons/button_click.ogg"),
+        };
+        if let Some(mut tracker) = world.get_resource_mut::<LoadingTracker>() {
+            tracker.track("fonts/font_regular.ttf", assets.font_reg.clone());
+            tracker.track("fonts/font_it.ttf", assets.font_it.clone());
+            tracker.track("buttons/menu_background.png", assets.bg_scroll.clone());
+            tracker.track("buttons/button_hover.ogg", assets.sfx_hover.clone());
+            tracker.track("buttons/button_click.ogg", assets.sfx_click.clone());
         }
+        assets
     }
 }
@@ -0,0 +1,252 @@
+use crate::cli::arg_value;
+use crate::data_format::{DataAssetPlugin, data_file};
+use crate::loading::LoadingTracker;
+use crate::localization::Locale;
+use crate::stream_event::{Permission, StreamEvent, StreamEventKind};
+use bevy::prelude::*;
+use bevy_inspector_egui::{bevy_egui, egui};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Права, лимиты и бан-лист для зрительских команд.
+///
+/// Настройки — `data/moderation.json`. Отклонённые команды пишутся в
+/// `moderation_log.jsonl` (`--moderation-log`).
+pub struct ModerationPlugin;
+
+/// `F8` мгновенно выключает/включает все эффекты из чата; пока выключены — надпись сверху.
+pub struct ModerationPanelPlugin;
+
+impl Plugin for ModerationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(DataAssetPlugin::<ModerationConfig>::default())
+            .init_resource::<Moderation>()
+            .init_resource::<ModerationLogPath>()
+            .add_event::<CommandRejected>()
+            .add_systems(Startup, load_moderation_config)
+            .add_systems(Update, (apply_moderation_config, log_rejected_commands));
+    }
+}
+
+impl Plugin for ModerationPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, toggle_chat_effects)
+            .add_systems(
+                bevy_egui::EguiPrimaryContextPass,
+                ui_chat_effects_disabled
+                    .run_if(resource_exists::<crate::egui_setup::EguiConfigured>)
+                    .run_if(|m: Res<Moderation>| !m.enabled),
+            );
+    }
+}
+
+const TOGGLE_KEY: KeyCode = KeyCode::F8;
+const MODERATION_CONFIG: &str = "data/moderation.json";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub max: u32,
+    pub per_seconds: f64,
+}
+
+#[derive(Debug, Clone, Deserialize, Asset, TypePath)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModerationConfig {
+    /// Лимит на все команды из чата вместе.
+    pub global_limit: RateLimit,
+    /// Лимит на одного зрителя.
+    pub user_limit: RateLimit,
+    /// Роли с этим уровнем и выше лимитам не подчиняются.
+    pub exempt_from_limits: Permission,
+    pub banned: Vec<String>,
+}
+
+impl Default for ModerationConfig {
+    fn default() -> Self {
+        Self {
+            global_limit: RateLimit { max: 20, per_seconds: 10.0 },
+            user_limit: RateLimit { max: 3, per_seconds: 10.0 },
+            exempt_from_limits: Permission::Moderator,
+            banned: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum RejectReason {
+    ChatEffectsDisabled,
+    Banned,
+    Permission { required: Permission, had: Permission },
+    GlobalRateLimit,
+    UserRateLimit,
+    /// Зритель видел волну `seen`, а она уже сменилась на `current`.
+    StaleWave { seen: u32, current: u32 },
+    /// Башни `tower`, которую зритель хотел улучшить или продать, уже нет.
+    StaleTower { tower: u32 },
+    /// Команда пролежала в очереди дольше допустимого.
+    Expired,
+}
+
+/// Команда зрителя, которую модерация не пропустила.
+#[derive(Event, Debug, Clone, Serialize)]
+pub struct CommandRejected {
+    pub user: String,
+    pub kind: StreamEventKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(flatten)]
+    pub reason: RejectReason,
+}
+
+#[derive(Resource)]
+pub struct Moderation {
+    pub config: ModerationConfig,
+    /// Выключатель стримера: `false` — эффекты из чата не применяются вообще.
+    pub enabled: bool,
+    global_hits: VecDeque<f64>,
+    user_hits: HashMap<String, VecDeque<f64>>,
+}
+
+impl Default for Moderation {
+    fn default() -> Self {
+        Self {
+            config: ModerationConfig::default(),
+            enabled: true,
+            global_hits: VecDeque::new(),
+            user_hits: HashMap::new(),
+        }
+    }
+}
+
+impl Moderation {
+    /// Можно ли вообще реагировать на этого зрителя (без учёта прав и лимитов).
+    pub fn allows(&self, user: &str) -> bool {
+        self.enabled && !self.config.banned.iter().any(|b| b.eq_ignore_ascii_case(user))
+    }
+
+    /// Проверяет, может ли событие `ev` запустить действие с правом `required`.
+    /// При успехе засчитывает его в лимиты; `now` — секунды реального времени.
+    pub fn check(&mut self, ev: &StreamEvent, required: Permission, now: f64) -> Result<(), RejectReason> {
+        if !self.enabled {
+            return Err(RejectReason::ChatEffectsDisabled);
+        }
+        let user = ev.user().to_lowercase();
+        if !self.allows(&user) {
+            return Err(RejectReason::Banned);
+        }
+        let had = ev.roles().level();
+        if had < required {
+            return Err(RejectReason::Permission { required, had });
+        }
+
+        // Платные события (биты, донаты, награды) не лимитируем — только команды чата.
+        if ev.kind() != StreamEventKind::Chat || had >= self.config.exempt_from_limits {
+            return Ok(());
+        }
+
+        let global = self.config.global_limit;
+        let per_user = self.config.user_limit;
+        prune(&mut self.global_hits, now - global.per_seconds);
+        if self.global_hits.len() >= global.max as usize {
+            return Err(RejectReason::GlobalRateLimit);
+        }
+        let hits = self.user_hits.entry(user).or_default();
+        prune(hits, now - per_user.per_seconds);
+        if hits.len() >= per_user.max as usize {
+            return Err(RejectReason::UserRateLimit);
+        }
+
+        hits.push_back(now);
+        self.global_hits.push_back(now);
+        Ok(())
+    }
+}
+
+fn prune(hits: &mut VecDeque<f64>, older_than: f64) {
+    while hits.front().is_some_and(|t| *t < older_than) {
+        hits.pop_front();
+    }
+}
+
+#[derive(Resource)]
+struct ModerationConfigHandle(Handle<ModerationConfig>);
+
+#[derive(Resource, Clone)]
+pub struct ModerationLogPath(pub PathBuf);
+
+impl Default for ModerationLogPath {
+    fn default() -> Self {
+        Self(arg_value("--moderation-log").unwrap_or_else(|| "moderation_log.jsonl".to_string()).into())
+    }
+}
+
+fn load_moderation_config(mut commands: Commands, asset_server: Res<AssetServer>, mut tracker: ResMut<LoadingTracker>) {
+    let path = data_file(MODERATION_CONFIG);
+    let handle: Handle<ModerationConfig> = asset_server.load(path.clone());
+    tracker.track(path, handle.clone());
+    commands.insert_resource(ModerationConfigHandle(handle));
+}
+
+fn apply_moderation_config(
+    mut events: EventReader<AssetEvent<ModerationConfig>>,
+    handle: Res<ModerationConfigHandle>,
+    configs: Res<Assets<ModerationConfig>>,
+    mut moderation: ResMut<Moderation>,
+) {
+    for ev in events.read() {
+        if (ev.is_loaded_with_dependencies(&handle.0) || ev.is_modified(&handle.0))
+            && let Some(config) = configs.get(&handle.0)
+        {
+            info!("Moderation config applied: {} banned viewers", config.banned.len());
+            moderation.config = config.clone();
+        }
+    }
+}
+
+fn toggle_chat_effects(keyboard: Res<ButtonInput<KeyCode>>, mut moderation: ResMut<Moderation>) {
+    if keyboard.just_pressed(TOGGLE_KEY) {
+        moderation.enabled = !moderation.enabled;
+        if moderation.enabled {
+            info!("Chat effects enabled");
+        } else {
+            warn!("Chat effects DISABLED by streamer");
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct RejectLogLine<'a> {
+    unix_time: u64,
+    #[serde(flatten)]
+    rejected: &'a CommandRejected,
+}
+
+fn log_rejected_commands(mut ev: EventReader<CommandRejected>, path: Res<ModerationLogPath>) {
+    for rejected in ev.read() {
+        warn!("Rejected {:?} from {}: {:?}", rejected.kind, rejected.user, rejected.reason);
+        let unix_time = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let Ok(json) = serde_json::to_string(&RejectLogLine { unix_time, rejected }) else { continue; };
+        let written = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path.0)
+            .and_then(|mut f| writeln!(f, "{}", json));
+        if let Err(e) = written {
+            error!("Moderation log: cannot write {}: {}", path.0.display(), e);
+        }
+    }
+}
+
+fn ui_chat_effects_disabled(mut egui_ctx: bevy_egui::EguiContexts, locale: Res<Locale>) {
+    let Ok(ctx) = egui_ctx.ctx_mut() else { return; };
+    egui::Area::new(egui::Id::new("chat_effects_disabled"))
+        .anchor(egui::Align2::CENTER_TOP, [0.0, 12.0])
+        .show(ctx, |ui| {
+            ui.colored_label(egui::Color32::from_rgb(230, 80, 60), locale.t("moderation.chat_disabled"));
+        });
+}
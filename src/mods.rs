@@ -0,0 +1,142 @@
+use crate::data_format::resolve_data_file;
+use crate::data_load::{DataErrors, assets_dir};
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::path::Path;
+
+/// Паки контента из `assets/mods/<папка>/`: в каждом `mod.json` и любые из файлов
+/// `data/towers`, `data/projectiles`, `data/enemies`, `data/waves` (`.json`, `.ron` или `.toml`) с той же
+/// схемой, что и у игры.
+///
+/// Паки применяются поверх базовых данных по возрастанию `load_order`, при равном — по `id`.
+/// Башни, снаряды и враги сливаются по `id`: запись из следующего пака заменяет прежнюю целиком,
+/// `extends` видит записи из всех паков. Расписание волн заменяется целиком последним паком,
+/// где оно есть. Таблицы переводов `locale/<язык>` дополняют базовые построчно, см.
+/// [`crate::localization`]. Уровни `data/levels/<id>` добавляются или заменяют базовые по `id`,
+/// см. [`crate::level`]. Паки ищутся при сборке приложения, поэтому новый пак требует перезапуска.
+pub struct ModsPlugin;
+
+impl Plugin for ModsPlugin {
+    fn build(&self, app: &mut App) {
+        let packs = ModPacks::discover(&assets_dir());
+        for pack in &packs.packs {
+            info!("Content pack '{}' {} from {}", pack.manifest.id, pack.manifest.version, pack.dir);
+        }
+        app.init_resource::<DataErrors>();
+        app.world_mut().resource_mut::<DataErrors>().0.extend(packs.errors.iter().cloned());
+        for e in &packs.errors {
+            error!("{}", e);
+        }
+        app.insert_resource(packs);
+    }
+}
+
+pub const MODS_DIR: &str = "mods";
+pub const MOD_MANIFEST: &str = "mod.json";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModManifest {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    /// Паки с большим значением применяются позже и перекрывают остальные.
+    #[serde(default)]
+    pub load_order: i32,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone)]
+pub struct ModPack {
+    pub manifest: ModManifest,
+    /// Путь относительно `assets`, например `mods/winter`.
+    pub dir: String,
+}
+
+/// Включённые паки в порядке применения.
+#[derive(Resource, Debug, Default)]
+pub struct ModPacks {
+    pub packs: Vec<ModPack>,
+    /// Паки с битым манифестом или повторным `id`; они пропущены.
+    pub errors: Vec<String>,
+}
+
+impl ModPacks {
+    /// Паки из `assets/mods`; нет папки — нет паков.
+    pub fn discover(assets: &Path) -> Self {
+        let mut found = Self::default();
+        let Ok(dirs) = std::fs::read_dir(assets.join(MODS_DIR)) else { return found; };
+        let mut dirs: Vec<_> = dirs.filter_map(|d| d.ok()).filter(|d| d.path().is_dir()).collect();
+        dirs.sort_by_key(|d| d.file_name());
+
+        for dir in dirs {
+            let dir = format!("{}/{}", MODS_DIR, dir.file_name().to_string_lossy());
+            let manifest = format!("{}/{}", dir, MOD_MANIFEST);
+            let parsed = std::fs::read_to_string(assets.join(&manifest))
+                .map_err(|e| e.to_string())
+                .and_then(|text| serde_json::from_str::<ModManifest>(&text).map_err(|e| e.to_string()));
+            match parsed {
+                Err(e) => found.errors.push(format!("{}: {}, the pack is skipped", manifest, e)),
+                Ok(m) if !m.enabled => info!("Content pack '{}' is disabled", m.id),
+                Ok(m) if found.packs.iter().any(|p| p.manifest.id == m.id) => found
+                    .errors
+                    .push(format!("{}: duplicate pack id '{}', the pack is skipped", manifest, m.id)),
+                Ok(manifest) => found.packs.push(ModPack { manifest, dir }),
+            }
+        }
+        found.packs.sort_by(|a, b| {
+            (a.manifest.load_order, &a.manifest.id).cmp(&(b.manifest.load_order, &b.manifest.id))
+        });
+        found
+    }
+
+    /// Все версии файла данных `file` (`data/towers.json`) в порядке применения:
+    /// базовая, затем из паков, где он есть. Формат в каждом слое свой, см. [`resolve_data_file`].
+    pub fn layers(&self, assets: &Path, file: &str) -> Vec<String> {
+        // Базового файла нет — пусть об этом скажет загрузка.
+        let base = resolve_data_file(assets, file).unwrap_or_else(|| file.to_string());
+        let packs = self.packs.iter().filter_map(|p| resolve_data_file(assets, &format!("{}/{}", p.dir, file)));
+        std::iter::once(base).chain(packs).collect()
+    }
+}
+
+/// Сливает записи слоёв по `id`: запись следующего слоя занимает место прежней.
+/// Возвращает записи с файлом-источником и сообщения о совпадениях `id` между паками.
+pub fn merge_layers<'a>(
+    kind: &str,
+    layers: impl IntoIterator<Item = (&'a str, &'a [serde_json::Value])>,
+) -> (Vec<(String, serde_json::Value)>, Vec<String>) {
+    let mut merged: Vec<(String, serde_json::Value)> = Vec::new();
+    let mut conflicts = Vec::new();
+    for (file, values) in layers {
+        for value in values {
+            let id = value.get("id").and_then(|id| id.as_str());
+            // Повтор внутри одного файла оставляем: о нём скажет разбор записей.
+            let previous = id.and_then(|id| {
+                merged.iter().position(|(f, v)| f != file && v.get("id").and_then(|v| v.as_str()) == Some(id))
+            });
+            let (Some(id), Some(previous)) = (id, previous) else {
+                merged.push((file.to_string(), value.clone()));
+                continue;
+            };
+            let overridden = &merged[previous].0;
+            // Базовые данные паки для того и меняют; совпадение между паками — конфликт.
+            if overridden.starts_with(MODS_DIR) {
+                conflicts.push(format!(
+                    "{}: {} '{}' is also defined in {}, the later pack wins",
+                    file, kind, id, overridden
+                ));
+            } else {
+                info!("{}: {} '{}' overrides {}", file, kind, id, overridden);
+            }
+            merged[previous] = (file.to_string(), value.clone());
+        }
+    }
+    (merged, conflicts)
+}
@@ -1,9 +1,13 @@
-use bevy::math::{ Vec3};
+use bevy::math::{FloatOrd, Vec3};
 use bevy::prelude::*;
 use crate::AppState;
+use crate::fixed_step::TranslationInterpolation;
 use crate::blood::SpawnBlood;
-use crate::target::{Health, Target};
+use crate::loading::LoadingTracker;
+use crate::target::{Health, Slowed, SpawnedBy, Target, TargetDamaged, TargetKilled, WaveTarget};
 use bevy::audio::{AudioSource};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 pub struct ProjectilePlugin;
 
@@ -11,56 +15,223 @@ impl Plugin for ProjectilePlugin {
     fn build(&self, app: &mut App){
         app.register_type::<Projectile>()
             .add_event::<DeathEvent>()
-            .add_systems(Startup, load_assets)
-            .add_systems(Update, (
+            .add_event::<SpawnBlood>()
+            .add_event::<ProjectileImpact>()
+            .add_systems(FixedUpdate, (
                 projectile_fly,
                 projectile_collision,
-                projectile_despawn,
-                play_death_sound).run_if(in_state(AppState::InGame)));
+                projectile_despawn).chain().run_if(in_state(AppState::InGame)));
 
         return;
     }
 }
 
+/// Звук смерти цели, эффекты и звуки попаданий.
+pub struct ProjectileViewPlugin;
+
+impl Plugin for ProjectileViewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_assets)
+            .add_systems(Update, (play_death_sound, spawn_impact_effects).run_if(in_state(AppState::InGame)))
+            .add_systems(Update, despawn_impact_effects);
+    }
+}
+
+/// Высота над ногами цели, куда целятся башни.
+pub const AIM_HEIGHT: f32 = 0.3;
+pub const DEFAULT_LIFETIME: f32 = 2.0;
+pub const DEFAULT_HIT_RADIUS: f32 = 0.4;
+const GRAVITY: f32 = 9.8;
+/// Доля скорости, на которую самонаводящийся снаряд доворачивает за секунду.
+const HOMING_TURN_RATE: f32 = 6.0;
+const IMPACT_VFX_SECONDS: f32 = 0.5;
+
+/// Как летит снаряд.
+#[derive(Reflect, Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectileBehavior {
+    #[default]
+    Straight,
+    /// Доворачивает за своей целью, пока она жива.
+    Homing,
+    /// Летит по дуге и падает туда, где цель была в момент выстрела.
+    Ballistic,
+}
+
+/// Что ещё делает попадание, кроме урона по поражённой цели.
+#[derive(Reflect, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum OnHitEffect {
+    /// Урон остальным целям в радиусе от точки попадания.
+    Splash { radius: f32, damage: f32 },
+    /// Скорость цели умножается на `factor` на `seconds` секунд.
+    Slow { factor: f32, seconds: f32 },
+}
+
+/// Описание снаряда из `data/projectiles.json`; башни ссылаются на него по `id`.
+#[derive(Reflect, Debug, Clone, Default)]
+pub struct ProjectileDef {
+    pub id: String,
+    pub scene: Handle<Scene>,
+    pub speed: f32,
+    pub scale: f32,
+    pub lifetime: f32,
+    pub hit_radius: f32,
+    pub behavior: ProjectileBehavior,
+    pub on_hit: Vec<OnHitEffect>,
+    pub impact_scene: Option<Handle<Scene>>,
+    pub impact_sound: Option<Handle<AudioSource>>,
+}
+
+#[derive(Resource, Default)]
+pub struct ProjectileDB {
+    pub defs: HashMap<String, ProjectileDef>,
+}
+
 #[derive(Reflect, Component, Default)]
 #[reflect(Component)]
+#[require(TranslationInterpolation)]
 pub struct Projectile{
-    pub speed: f32,
-    pub direction: Vec3,
+    pub velocity: Vec3,
     pub life_timer: Timer,
     pub damage: f32,
+    /// Башня, выпустившая снаряд.
+    pub source: Option<Entity>,
+    /// Цель, за которой доворачивает самонаводящийся снаряд.
+    pub target: Option<Entity>,
+    pub def: ProjectileDef,
+}
+
+impl Projectile {
+    /// Снаряд, летящий из точки выстрела в точку `to` относительно неё.
+    pub fn launch(def: &ProjectileDef, to: Vec3, damage: f32, source: Entity, target: Entity) -> Self {
+        let velocity = match def.behavior {
+            ProjectileBehavior::Straight | ProjectileBehavior::Homing => to.normalize_or_zero() * def.speed,
+            // Время полёта как у прямого выстрела; вертикальная скорость гасит падение.
+            ProjectileBehavior::Ballistic => {
+                let t = (to.length() / def.speed).max(f32::EPSILON);
+                to / t + Vec3::Y * 0.5 * GRAVITY * t
+            }
+        };
+        Self {
+            velocity,
+            life_timer: Timer::from_seconds(def.lifetime, TimerMode::Once),
+            damage,
+            source: Some(source),
+            target: Some(target),
+            def: def.clone(),
+        }
+    }
+}
+
+/// Попадание снаряда — для эффекта и звука.
+#[derive(Event, Debug, Clone)]
+pub struct ProjectileImpact {
+    pub pos: Vec3,
+    pub scene: Option<Handle<Scene>>,
+    pub sound: Option<Handle<AudioSource>>,
 }
 
-fn projectile_fly(mut projectiles: Query<(&mut Transform, &Projectile)>, time: Res<Time>){
-    for (mut transform, projectile) in projectiles.iter_mut(){
-        transform.translation += projectile.direction*projectile.speed*time.delta_secs();
+fn projectile_fly(
+    mut projectiles: Query<(&mut Transform, &mut Projectile)>,
+    targets: Query<&Transform, (With<Target>, Without<Projectile>)>,
+    time: Res<Time>,
+){
+    let dt = time.delta_secs();
+    for (mut transform, mut projectile) in projectiles.iter_mut(){
+        match projectile.def.behavior {
+            ProjectileBehavior::Straight => {}
+            ProjectileBehavior::Homing => {
+                if let Some(target) = projectile.target.and_then(|t| targets.get(t).ok()) {
+                    let to = target.translation + Vec3::Y * AIM_HEIGHT - transform.translation;
+                    let desired = to.normalize_or_zero() * projectile.def.speed;
+                    let turned = projectile.velocity.lerp(desired, (HOMING_TURN_RATE * dt).min(1.0));
+                    projectile.velocity = turned.normalize_or_zero() * projectile.def.speed;
+                }
+            }
+            ProjectileBehavior::Ballistic => projectile.velocity.y -= GRAVITY * dt,
+        }
+        transform.translation += projectile.velocity * dt;
+        if projectile.def.behavior != ProjectileBehavior::Straight && projectile.velocity != Vec3::ZERO {
+            transform.rotation = Quat::from_rotation_arc(-Vec3::Z, projectile.velocity.normalize());
+        }
     }
 }
 
+type TargetHit = (
+    Entity,
+    &'static mut Transform,
+    &'static mut Health,
+    Option<&'static SpawnedBy>,
+    Option<&'static WaveTarget>,
+);
+
+#[derive(bevy::ecs::system::SystemParam)]
+struct HitEvents<'w> {
+    blood: EventWriter<'w, SpawnBlood>,
+    death: EventWriter<'w, DeathEvent>,
+    damage: EventWriter<'w, TargetDamaged>,
+    killed: EventWriter<'w, TargetKilled>,
+    impact: EventWriter<'w, ProjectileImpact>,
+}
+
 fn projectile_collision(
     mut commands: Commands,
-    projectiles: Query<(Entity,&mut GlobalTransform), With<Projectile>>,
-    mut targets: Query<(Entity, &mut Transform, &mut Health), With<Target>>,
-    mut blood_ev: EventWriter<SpawnBlood>,
-    mut death_ev: EventWriter<DeathEvent>,
+    projectiles: Query<(Entity, &Transform, &Projectile), Without<Target>>,
+    mut targets: Query<TargetHit, With<Target>>,
+    mut events: HitEvents,
 ){
-    
-    //ToDo: change iteration from proj->target to target->proj
-    for (projectile, projectile_transform) in projectiles{
-        for (te, target_transform, mut health) in &mut targets{
-            let target_pos = target_transform.translation;
-            if Vec3::distance(projectile_transform.translation(), target_pos) < 0.4{
-                health.0 -= 15.0;
-                commands.entity(projectile).despawn();
+    for (projectile, projectile_transform, pr) in projectiles{
+        let pos = projectile_transform.translation;
+        // Убитые на этом шаге ещё не удалены — их пропускаем.
+        let hit = targets
+            .iter()
+            .filter(|(_, t, health, ..)| health.0 > 0.0 && t.translation.distance(pos) < pr.def.hit_radius)
+            .min_by_key(|(_, t, ..)| FloatOrd(t.translation.distance_squared(pos)))
+            .map(|(e, ..)| e);
+        let Some(hit) = hit else { continue; };
 
-                if health.0 <= 0.0 {
-                    commands.entity(te).despawn();
+        commands.entity(projectile).despawn();
+        events.impact.write(ProjectileImpact {
+            pos,
+            scene: pr.def.impact_scene.clone(),
+            sound: pr.def.impact_sound.clone(),
+        });
 
-                    blood_ev.write(SpawnBlood{pos: target_pos});
-                    death_ev.write(DeathEvent{pos: target_pos});
+        let mut struck = vec![(hit, pr.damage)];
+        for effect in &pr.def.on_hit {
+            match *effect {
+                OnHitEffect::Splash { radius, damage } => struck.extend(
+                    targets
+                        .iter()
+                        .filter(|(e, t, health, ..)| *e != hit && health.0 > 0.0 && t.translation.distance(pos) <= radius)
+                        .map(|(e, ..)| (e, damage)),
+                ),
+                OnHitEffect::Slow { factor, seconds } => {
+                    commands.entity(hit).try_insert(Slowed::new(factor, seconds));
                 }
             }
         }
+
+        for (te, amount) in struck {
+            let Ok((_, target_transform, mut health, spawned_by, wave)) = targets.get_mut(te) else { continue; };
+            let target_pos = target_transform.translation;
+            let damage = amount.min(health.0.max(0.0));
+            health.0 -= amount;
+            events.damage.write(TargetDamaged { amount: damage, spawned_by: spawned_by.map(|s| s.0.clone()) });
+
+            if health.0 <= 0.0 {
+                commands.entity(te).despawn();
+
+                events.blood.write(SpawnBlood{pos: target_pos});
+                events.death.write(DeathEvent{pos: target_pos});
+                events.killed.write(TargetKilled {
+                    tower: pr.source,
+                    wave: wave.map(|w| w.0),
+                    overkill: amount - damage,
+                });
+            }
+        }
     }
     return;
 }
@@ -72,6 +243,8 @@ struct DeathEvent {
 #[derive(Resource, Deref)]
 struct DeathSound(Handle<AudioSource>);
 
+const DEATH_SOUND: &str = "ogg/death_01.ogg";
+
 
 fn play_death_sound(
     mut commands: Commands,
@@ -106,8 +279,44 @@ fn projectile_despawn(mut commands: Commands,
 
 fn load_assets(
     mut commands: Commands,
-    asset_server: Res<AssetServer>
+    asset_server: Res<AssetServer>,
+    mut tracker: ResMut<LoadingTracker>,
     ){
-    let death_sound = asset_server.load("ogg/death_01.ogg");
+    let death_sound = asset_server.load(DEATH_SOUND);
+    tracker.track(DEATH_SOUND, death_sound.clone());
     commands.insert_resource(DeathSound(death_sound));
-}
\ No newline at end of file
+}
+
+/// Эффект попадания живёт недолго.
+#[derive(Component)]
+struct ImpactEffect(Timer);
+
+fn spawn_impact_effects(mut commands: Commands, mut impacts: EventReader<ProjectileImpact>) {
+    for impact in impacts.read() {
+        if let Some(scene) = &impact.scene {
+            commands.spawn((
+                Name::new("ProjectileImpact"),
+                SceneRoot(scene.clone()),
+                Transform::from_translation(impact.pos),
+                ImpactEffect(Timer::from_seconds(IMPACT_VFX_SECONDS, TimerMode::Once)),
+            ));
+        }
+        if let Some(sound) = &impact.sound {
+            commands.spawn((
+                Name::new("ImpactSound"),
+                Transform::from_translation(impact.pos),
+                GlobalTransform::default(),
+                AudioPlayer(sound.clone()),
+                PlaybackSettings::DESPAWN.with_spatial(true),
+            ));
+        }
+    }
+}
+
+fn despawn_impact_effects(mut commands: Commands, mut effects: Query<(Entity, &mut ImpactEffect)>, time: Res<Time>) {
+    for (e, mut effect) in &mut effects {
+        if effect.0.tick(time.delta()).finished() {
+            commands.entity(e).despawn();
+        }
+    }
+}
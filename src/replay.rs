@@ -0,0 +1,223 @@
+use crate::AppState;
+use crate::cli::arg_value;
+use crate::fixed_step::SimulationTick;
+use crate::game_action::{ActionApplied, GameActions};
+use crate::level::{Levels, MatchLevel};
+use crate::rng::GameRng;
+use crate::stream_event::StreamEvent;
+use anyhow::Context;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Запись и воспроизведение сессии: сид плюс все принятые действия и события чата
+/// с номерами шагов симуляции. Благодаря [`GameRng`] и фиксированному шагу реплей
+/// повторяет игру в точности.
+///
+/// ```text
+/// {"version": 1, "seed": 42, "level": "meadow"}
+/// {"tick": 300, "command": {"action": {"cmd": "place_tower", "tower": "basic", "pos": [2.0, 0.0, 1.0]}}}
+/// {"tick": 410, "stream": {"type": "chat", "user": "viewer1", "text": "!spawn"}}
+/// {"tick": 411, "command": {"action": {"cmd": "spawn_enemy", "count": 1, "boss": false}, "viewer": "viewer1"}}
+/// ```
+///
+/// Запуск: `--record-replay out.jsonl` и/или `--replay in.jsonl`. Во время воспроизведения
+/// реакции на чат выключены — их результат уже записан как действия.
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplayConfig>()
+            .add_systems(Startup, load_replay)
+            .add_systems(OnEnter(AppState::InGame), start_replay_recording)
+            .add_systems(
+                FixedFirst,
+                (
+                    play_replay.run_if(resource_exists::<ReplayPlayback>),
+                    record_replay.run_if(resource_exists::<ReplayRecorder>),
+                )
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+pub const REPLAY_VERSION: u32 = 1;
+
+#[derive(Resource, Clone)]
+pub struct ReplayConfig {
+    pub play: Option<PathBuf>,
+    pub record: Option<PathBuf>,
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        Self {
+            play: arg_value("--replay").map(PathBuf::from),
+            record: arg_value("--record-replay").map(PathBuf::from),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayHeader {
+    pub version: u32,
+    pub seed: u64,
+    /// Id уровня; старые реплеи играются на выбранном.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayInput {
+    Command(ActionApplied),
+    Stream(StreamEvent),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayEntry {
+    pub tick: u64,
+    #[serde(flatten)]
+    pub input: ReplayInput,
+}
+
+/// Идёт воспроизведение; пока ресурс есть, чат на игру не влияет.
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    pub name: String,
+    entries: VecDeque<ReplayEntry>,
+}
+
+impl ReplayPlayback {
+    pub fn load(path: &Path) -> anyhow::Result<(ReplayHeader, Self)> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read {}", path.display()))?;
+        let name = path.display().to_string();
+        let mut lines = text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty());
+
+        let (_, first) = lines.next().with_context(|| format!("{}: empty replay", name))?;
+        let header: ReplayHeader =
+            serde_json::from_str(first).with_context(|| format!("{}:1: bad header", name))?;
+        if header.version != REPLAY_VERSION {
+            anyhow::bail!("{}: replay version {} is not supported (expected {})", name, header.version, REPLAY_VERSION);
+        }
+
+        let mut entries = Vec::new();
+        for (i, line) in lines {
+            let entry: ReplayEntry =
+                serde_json::from_str(line).with_context(|| format!("{}:{}", name, i + 1))?;
+            entries.push(entry);
+        }
+        // Стабильная сортировка: порядок внутри одного шага сохраняется.
+        entries.sort_by_key(|e| e.tick);
+        Ok((header, Self { name, entries: entries.into() }))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[derive(Resource)]
+struct ReplayRecorder {
+    out: BufWriter<File>,
+}
+
+impl ReplayRecorder {
+    fn write_line(&mut self, value: &impl Serialize) {
+        let written = serde_json::to_string(value)
+            .map_err(std::io::Error::other)
+            .and_then(|json| writeln!(self.out, "{}", json))
+            .and_then(|_| self.out.flush());
+        if let Err(e) = written {
+            warn!("Replay recorder: {}", e);
+        }
+    }
+}
+
+fn load_replay(mut commands: Commands, config: Res<ReplayConfig>, mut levels: ResMut<Levels>) {
+    let Some(path) = &config.play else { return; };
+    match ReplayPlayback::load(path) {
+        Ok((header, playback)) => {
+            info!("Playing replay {} ({} inputs, seed {})", playback.name, playback.entries.len(), header.seed);
+            commands.insert_resource(GameRng::new(header.seed));
+            if let Some(level) = &header.level
+                && !levels.select(level)
+            {
+                warn!("Replay is from unknown level '{}', playing on '{}'", level, levels.selected);
+            }
+            commands.insert_resource(playback);
+        }
+        Err(e) => error!("Replay: {:#}", e),
+    }
+}
+
+fn start_replay_recording(
+    mut commands: Commands,
+    config: Res<ReplayConfig>,
+    rng: Res<GameRng>,
+    level: Res<MatchLevel>,
+    mut started: Local<bool>,
+) {
+    if *started {
+        return;
+    }
+    *started = true;
+
+    let Some(path) = &config.record else { return; };
+    match File::create(path) {
+        Ok(file) => {
+            info!("Recording replay to {}", path.display());
+            let mut recorder = ReplayRecorder { out: BufWriter::new(file) };
+            recorder.write_line(&ReplayHeader { version: REPLAY_VERSION, seed: rng.seed(), level: level.0.clone() });
+            commands.insert_resource(recorder);
+        }
+        Err(e) => error!("Replay recorder: cannot create {}: {}", path.display(), e),
+    }
+}
+
+fn play_replay(
+    mut playback: ResMut<ReplayPlayback>,
+    tick: Res<SimulationTick>,
+    mut actions: GameActions,
+    mut stream_ev: EventWriter<StreamEvent>,
+) {
+    if playback.is_finished() {
+        return;
+    }
+    while playback.entries.front().is_some_and(|e| e.tick <= tick.0) {
+        let Some(entry) = playback.entries.pop_front() else { break; };
+        match entry.input {
+            ReplayInput::Command(ActionApplied { action, viewer }) => {
+                if let Err(e) = actions.apply_as(&action, viewer.as_deref()) {
+                    warn!("Replay desync at tick {}: {:?} failed: {}", entry.tick, action, e);
+                }
+            }
+            ReplayInput::Stream(ev) => {
+                stream_ev.write(ev);
+            }
+        }
+    }
+    if playback.is_finished() {
+        info!("Replay {} finished at tick {}", playback.name, tick.0);
+    }
+}
+
+fn record_replay(
+    mut recorder: ResMut<ReplayRecorder>,
+    tick: Res<SimulationTick>,
+    mut applied: EventReader<ActionApplied>,
+    mut stream_ev: EventReader<StreamEvent>,
+) {
+    // События чата пишем раньше действий: в живой игре действие всегда следует за ним.
+    for ev in stream_ev.read() {
+        recorder.write_line(&ReplayEntry { tick: tick.0, input: ReplayInput::Stream(ev.clone()) });
+    }
+    for applied in applied.read() {
+        recorder.write_line(&ReplayEntry { tick: tick.0, input: ReplayInput::Command(applied.clone()) });
+    }
+}
@@ -0,0 +1,80 @@
+use crate::cli::arg_value;
+use bevy::prelude::*;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+/// Детерминированный случай: один сид (`--seed 42`) задаёт всю игру.
+///
+/// Два независимых потока: `gameplay` — всё, что влияет на исход (виды целей и т.п.),
+/// `cosmetic` — эффекты. Сколько бы крови ни разлилось, геймплей не разъедется.
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameRng>();
+    }
+}
+
+const GAMEPLAY_STREAM: u64 = 0;
+const COSMETIC_STREAM: u64 = 1;
+
+#[derive(Resource, Clone)]
+pub struct GameRng {
+    seed: u64,
+    pub gameplay: ChaCha8Rng,
+    pub cosmetic: ChaCha8Rng,
+}
+
+impl Default for GameRng {
+    /// Сид из `--seed`, иначе случайный — он пишется в лог, чтобы забег можно было повторить.
+    fn default() -> Self {
+        let seed = match arg_value("--seed") {
+            Some(v) => v.parse().unwrap_or_else(|_| {
+                warn!("--seed must be an unsigned integer, got '{}'", v);
+                rand::rng().next_u64()
+            }),
+            None => rand::rng().next_u64(),
+        };
+        info!("Game seed: {}", seed);
+        Self::new(seed)
+    }
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        let stream = |stream| {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            rng.set_stream(stream);
+            rng
+        };
+        Self { seed, gameplay: stream(GAMEPLAY_STREAM), cosmetic: stream(COSMETIC_STREAM) }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Позиции обоих потоков — чтобы продолжить ту же последовательность после загрузки.
+    pub fn state(&self) -> RngState {
+        RngState {
+            seed: self.seed,
+            gameplay_pos: self.gameplay.get_word_pos(),
+            cosmetic_pos: self.cosmetic.get_word_pos(),
+        }
+    }
+
+    pub fn from_state(state: &RngState) -> Self {
+        let mut rng = Self::new(state.seed);
+        rng.gameplay.set_word_pos(state.gameplay_pos);
+        rng.cosmetic.set_word_pos(state.cosmetic_pos);
+        rng
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RngState {
+    pub seed: u64,
+    pub gameplay_pos: u128,
+    pub cosmetic_pos: u128,
+}
@@ -0,0 +1,419 @@
+use crate::AppState;
+use crate::cli::arg_value;
+use crate::economy::{Gold, Lives};
+use crate::fixed_step::SimulationTick;
+use crate::headless::Headless;
+use crate::level::{ActiveLevel, MatchLevel, saved_level_ready};
+use crate::projectile::Projectile;
+use crate::replay::ReplayPlayback;
+use crate::rng::{GameRng, RngState};
+use crate::target::{
+    AmbientSpawn, Boss, Health, PathFollower, SpawnedBy, Speed, Target, TargetCatalog, TargetKind, WaveTarget,
+    spawn_target,
+};
+use crate::tower::{
+    MAX_TOWER_LEVEL, NextTowerId, Sponsorship, Sponsorships, Tower, TowerDB, TowerFreeze, TowerId, TowerOwner,
+    TowerStats, tower_bundle, tower_stats,
+};
+use crate::wave::{WaveEnded, WaveSchedule, WaveState};
+use anyhow::Context;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Сохранение и загрузка идущего матча: башни, цели с здоровьем и местом на пути,
+/// волна, золото, жизни, уровень, таймеры и состояние [`GameRng`]. Снаряды в полёте не сохраняются.
+///
+/// В конце каждой волны — автосейв в `--autosave` (`autosave.json`). Загрузка — кнопкой
+/// в главном меню (берётся более свежий из двух файлов) или флагом `--load file.json`.
+pub struct SaveGamePlugin;
+
+/// `F5` — сохранить в `--save` (`savegame.json`).
+pub struct QuicksavePlugin;
+
+impl Plugin for SaveGamePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SaveGameConfig>()
+            .add_event::<LoadGameEvent>()
+            .add_systems(Startup, load_from_cli)
+            .add_systems(
+                Update,
+                (
+                    read_load_requests,
+                    autosave_on_wave_end
+                        .run_if(not(resource_exists::<ReplayPlayback>))
+                        .run_if(not(resource_exists::<Headless>)),
+                ),
+            )
+            // До `StateTransition`: матч входит в `InGame` уже восстановленным.
+            .add_systems(
+                PreUpdate,
+                apply_pending_load
+                    .run_if(resource_exists::<PendingLoad>)
+                    .run_if(resource_exists::<TowerDB>)
+                    .run_if(not(in_state(AppState::Loading)))
+                    .run_if(saved_level_ready),
+            );
+    }
+}
+
+impl Plugin for QuicksavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, quicksave.run_if(in_state(AppState::InGame)));
+    }
+}
+
+pub const SAVE_VERSION: u32 = 2;
+const QUICKSAVE_KEY: KeyCode = KeyCode::F5;
+
+#[derive(Resource, Clone)]
+pub struct SaveGameConfig {
+    pub path: PathBuf,
+    pub autosave: PathBuf,
+}
+
+impl Default for SaveGameConfig {
+    fn default() -> Self {
+        Self {
+            path: arg_value("--save").unwrap_or_else(|| "savegame.json".to_string()).into(),
+            autosave: arg_value("--autosave").unwrap_or_else(|| "autosave.json".to_string()).into(),
+        }
+    }
+}
+
+impl SaveGameConfig {
+    /// Самое свежее из ручного сохранения и автосейва.
+    pub fn latest(&self) -> Option<PathBuf> {
+        [&self.path, &self.autosave]
+            .into_iter()
+            .filter_map(|p| Some((std::fs::metadata(p).ok()?.modified().ok()?, p)))
+            .max_by_key(|(modified, _)| *modified)
+            .map(|(_, p)| p.clone())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveGame {
+    pub version: u32,
+    pub tick: u64,
+    pub rng: RngState,
+    pub wave: SavedWave,
+    pub gold: u32,
+    pub lives: u32,
+    pub next_tower_id: u32,
+    pub towers: Vec<SavedTower>,
+    pub targets: Vec<SavedTarget>,
+    #[serde(default)]
+    pub sponsorships: Vec<Sponsorship>,
+    /// Сколько секунд ещё заморожены башни.
+    #[serde(default)]
+    pub towers_frozen: Option<f32>,
+    /// Сколько секунд прошло с последнего фонового спавна, см. [`AmbientSpawn`].
+    #[serde(default)]
+    pub ambient_spawn: f32,
+    /// Id уровня; старые сохранения грузятся на текущий.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedWave {
+    pub index: u32,
+    pub pending: u32,
+    pub ended: u32,
+    /// Секунды с появления последней цели волны.
+    #[serde(default)]
+    pub spawn_elapsed: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedTower {
+    pub id: u32,
+    /// Id описания в `data/towers.json`.
+    pub def: String,
+    pub pos: [f32; 3],
+    pub level: u32,
+    pub invested: u32,
+    pub kills: u32,
+    /// Сколько секунд прошло с последнего выстрела.
+    pub cooldown: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedTarget {
+    /// Id врага из `data/enemies.json`: номер в каталоге меняется вместе с файлом и паками.
+    pub kind: String,
+    #[serde(default)]
+    pub boss: bool,
+    pub health: f32,
+    pub speed: f32,
+    /// Место на пути.
+    pub pos: [f32; 3],
+    /// Номер пути уровня и следующей точки на нём, см. [`PathFollower`].
+    #[serde(default)]
+    pub path: usize,
+    #[serde(default = "first_waypoint")]
+    pub waypoint: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wave: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spawned_by: Option<String>,
+}
+
+impl SaveGame {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+        let save: SaveGame =
+            serde_json::from_str(&text).with_context(|| format!("{}: bad save file", path.display()))?;
+        if save.version != SAVE_VERSION {
+            anyhow::bail!(
+                "{}: save version {} is not supported (expected {})",
+                path.display(),
+                save.version,
+                SAVE_VERSION
+            );
+        }
+        Ok(save)
+    }
+
+    /// Пишет во временный файл и переименовывает — оборванная запись не портит прошлое сохранение.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, json).with_context(|| format!("cannot write {}", tmp.display()))?;
+        std::fs::rename(&tmp, path).with_context(|| format!("cannot replace {}", path.display()))?;
+        Ok(())
+    }
+}
+
+fn first_waypoint() -> usize {
+    1
+}
+
+/// Загрузить сохранение из файла.
+#[derive(Event, Debug, Clone)]
+pub struct LoadGameEvent {
+    pub path: PathBuf,
+}
+
+/// Прочитанное сохранение ждёт `TowerDB`, чтобы восстановить башни.
+#[derive(Resource)]
+pub struct PendingLoad(pub SaveGame);
+
+type TowerQuery = (
+    &'static TowerId,
+    &'static Tower,
+    &'static TowerStats,
+    &'static Transform,
+    Option<&'static TowerOwner>,
+);
+type TargetQuery = (
+    &'static TargetKind,
+    &'static Health,
+    &'static Speed,
+    &'static Transform,
+    &'static PathFollower,
+    Has<Boss>,
+    Option<&'static WaveTarget>,
+    Option<&'static SpawnedBy>,
+);
+
+/// Сущности матча, которые заменяет загрузка или новый матч.
+pub type MatchEntities = Or<(With<Tower>, With<Target>, With<Projectile>)>;
+
+/// Всё, из чего собирается [`SaveGame`].
+#[derive(SystemParam)]
+pub struct GameSnapshot<'w, 's> {
+    tick: Res<'w, SimulationTick>,
+    rng: Res<'w, GameRng>,
+    wave: Res<'w, WaveState>,
+    gold: Res<'w, Gold>,
+    lives: Res<'w, Lives>,
+    next_tower_id: Res<'w, NextTowerId>,
+    sponsorships: Res<'w, Sponsorships>,
+    freeze: Res<'w, TowerFreeze>,
+    ambient: Res<'w, AmbientSpawn>,
+    level: Res<'w, MatchLevel>,
+    towers: Query<'w, 's, TowerQuery>,
+    targets: Query<'w, 's, TargetQuery, With<Target>>,
+}
+
+impl GameSnapshot<'_, '_> {
+    pub fn capture(&self) -> SaveGame {
+        let mut towers: Vec<_> = self
+            .towers
+            .iter()
+            .map(|(id, tower, stats, transform, owner)| SavedTower {
+                id: id.0,
+                def: stats.def_id.clone(),
+                pos: transform.translation.to_array(),
+                level: tower.level,
+                invested: tower.invested,
+                kills: tower.kills,
+                cooldown: tower.shooting_timer.elapsed_secs(),
+                owner: owner.map(|o| o.0.clone()),
+            })
+            .collect();
+        towers.sort_by_key(|t| t.id);
+
+        let targets = self
+            .targets
+            .iter()
+            .map(|(kind, health, speed, transform, follower, boss, wave, spawned_by)| SavedTarget {
+                kind: kind.0.clone(),
+                boss,
+                health: health.0,
+                speed: speed.0,
+                pos: transform.translation.to_array(),
+                path: follower.route,
+                waypoint: follower.next,
+                wave: wave.map(|w| w.0),
+                spawned_by: spawned_by.map(|s| s.0.clone()),
+            })
+            .collect();
+
+        SaveGame {
+            version: SAVE_VERSION,
+            tick: self.tick.0,
+            rng: self.rng.state(),
+            wave: SavedWave {
+                index: self.wave.index,
+                pending: self.wave.pending,
+                ended: self.wave.ended,
+                spawn_elapsed: self.wave.spawn_elapsed(),
+            },
+            gold: self.gold.0,
+            lives: self.lives.0,
+            next_tower_id: self.next_tower_id.0,
+            towers,
+            targets,
+            sponsorships: self.sponsorships.pending.clone(),
+            towers_frozen: self.freeze.0.as_ref().map(|t| t.remaining_secs()),
+            ambient_spawn: self.ambient.timer.elapsed_secs(),
+            level: self.level.0.clone(),
+        }
+    }
+}
+
+fn write_save(snapshot: &GameSnapshot, path: &Path) {
+    let save = snapshot.capture();
+    match save.save(path) {
+        Ok(()) => info!("Game saved to {} (wave {}, tick {})", path.display(), save.wave.index, save.tick),
+        Err(e) => error!("Save game: {:#}", e),
+    }
+}
+
+fn quicksave(keyboard: Res<ButtonInput<KeyCode>>, snapshot: GameSnapshot, config: Res<SaveGameConfig>) {
+    if keyboard.just_pressed(QUICKSAVE_KEY) {
+        write_save(&snapshot, &config.path);
+    }
+}
+
+fn autosave_on_wave_end(mut ev: EventReader<WaveEnded>, snapshot: GameSnapshot, config: Res<SaveGameConfig>) {
+    if ev.read().last().is_some() {
+        write_save(&snapshot, &config.autosave);
+    }
+}
+
+fn load_from_cli(mut commands: Commands) {
+    let Some(path) = arg_value("--load") else { return; };
+    match SaveGame::load(Path::new(&path)) {
+        Ok(save) => commands.insert_resource(PendingLoad(save)),
+        Err(e) => error!("Load game: {:#}", e),
+    }
+}
+
+fn read_load_requests(mut commands: Commands, mut ev: EventReader<LoadGameEvent>) {
+    for LoadGameEvent { path } in ev.read() {
+        match SaveGame::load(path) {
+            Ok(save) => {
+                info!("Loading {}", path.display());
+                commands.insert_resource(PendingLoad(save));
+            }
+            Err(e) => error!("Load game: {:#}", e),
+        }
+    }
+}
+
+fn apply_pending_load(
+    mut commands: Commands,
+    pending: Res<PendingLoad>,
+    (db, catalog, schedule): (Res<TowerDB>, Res<TargetCatalog>, Res<WaveSchedule>),
+    existing: Query<Entity, MatchEntities>,
+    mut state: ResMut<NextState<AppState>>,
+    (level, mut match_level): (Res<ActiveLevel>, ResMut<MatchLevel>),
+) {
+    let save = &pending.0;
+    for e in &existing {
+        commands.entity(e).despawn();
+    }
+
+    for saved in &save.towers {
+        let Some(def) = db.defs.get(&saved.def) else {
+            warn!("Save: unknown tower '{}', skipped", saved.def);
+            continue;
+        };
+        let mut bundle = tower_bundle(def, Vec3::from_array(saved.pos));
+        bundle.tower.level = saved.level.clamp(1, MAX_TOWER_LEVEL);
+        bundle.tower.invested = saved.invested;
+        bundle.tower.kills = saved.kills;
+        bundle.tower.shooting_timer.set_elapsed(Duration::from_secs_f32(saved.cooldown.max(0.0)));
+        bundle.stats = tower_stats(def, bundle.tower.level);
+        let mut tower = commands.spawn((bundle, TowerId(saved.id)));
+        if let Some(owner) = &saved.owner {
+            tower.insert(TowerOwner(owner.clone()));
+        }
+    }
+
+    for saved in &save.targets {
+        let Some(kind) = catalog.find(&saved.kind) else {
+            warn!("Save: unknown enemy '{}', skipped", saved.kind);
+            continue;
+        };
+        // Без своего пути цель стояла бы на месте и не давала волне закончиться.
+        if saved.path >= level.routes.len() {
+            warn!("Save: level '{}' has no path {}, target '{}' skipped", level.id, saved.path, saved.kind);
+            continue;
+        }
+        let follower = PathFollower { route: saved.path, next: saved.waypoint };
+        let mut target = spawn_target(&mut commands, &catalog, kind, saved.boss, Vec3::from_array(saved.pos), follower);
+        target.insert((Health(saved.health), Speed(saved.speed)));
+        if let Some(wave) = saved.wave {
+            target.insert(WaveTarget(wave));
+        }
+        if let Some(viewer) = &saved.spawned_by {
+            target.insert(SpawnedBy(viewer.clone()));
+        }
+    }
+
+    let saved = &save.wave;
+    let interval = schedule.wave(saved.index).interval;
+    commands.insert_resource(WaveState::restored(saved.index, saved.pending, saved.ended, interval, saved.spawn_elapsed));
+    commands.insert_resource(SimulationTick(save.tick));
+    commands.insert_resource(GameRng::from_state(&save.rng));
+    commands.insert_resource(Gold(save.gold));
+    commands.insert_resource(Lives(save.lives));
+    commands.insert_resource(NextTowerId(save.next_tower_id));
+    commands.insert_resource(Sponsorships { pending: save.sponsorships.clone() });
+    commands.insert_resource(TowerFreeze(
+        save.towers_frozen.map(|secs| Timer::from_seconds(secs, TimerMode::Once)),
+    ));
+    let mut ambient = AmbientSpawn::default();
+    ambient.timer.set_elapsed(Duration::from_secs_f32(save.ambient_spawn.max(0.0)));
+    commands.insert_resource(ambient);
+
+    info!(
+        "Game loaded: wave {}, {} towers, {} targets, tick {}",
+        save.wave.index,
+        save.towers.len(),
+        save.targets.len(),
+        save.tick
+    );
+    match_level.0 = Some(level.id.clone());
+    commands.remove_resource::<PendingLoad>();
+    state.set(AppState::InGame);
+}
@@ -0,0 +1,373 @@
+use crate::command_queue::CommandQueue;
+use crate::data_format::{DataAssetPlugin, data_file};
+use crate::loading::LoadingTracker;
+use crate::replay::ReplayPlayback;
+use crate::game_action::GameAction;
+use crate::moderation::{CommandRejected, Moderation};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// Собирает события из всех [`StreamEventSource`] в единый поток [`StreamEvent`]
+/// и ставит в [`CommandQueue`] действия по реакциям из `data/stream_reactions.json`.
+pub struct StreamEventPlugin;
+
+impl Plugin for StreamEventPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(DataAssetPlugin::<StreamReactionsFile>::default())
+            .add_event::<StreamEvent>()
+            .add_event::<ChatReply>()
+            .init_resource::<StreamSources>()
+            .add_systems(Startup, load_stream_reactions)
+            .add_systems(PreUpdate, poll_stream_sources)
+            .add_systems(Update, apply_stream_reactions.run_if(not(resource_exists::<ReplayPlayback>)))
+            .add_systems(PostUpdate, deliver_chat_replies);
+    }
+}
+
+/// Нормализованное событие стрима, независимо от того, откуда оно пришло.
+#[derive(Event, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamEvent {
+    Chat {
+        user: String,
+        text: String,
+        #[serde(default)]
+        roles: ViewerRoles,
+    },
+    Follow { user: String },
+    Sub {
+        user: String,
+        #[serde(default = "one_month")]
+        months: u32,
+    },
+    Bits {
+        user: String,
+        amount: u32,
+        /// Текст сообщения с битами.
+        #[serde(default)]
+        message: Option<String>,
+    },
+    Donation {
+        user: String,
+        amount: f32,
+        #[serde(default)]
+        currency: String,
+    },
+    Raid { user: String, viewers: u32 },
+    Redemption {
+        user: String,
+        reward: String,
+        #[serde(default)]
+        input: Option<String>,
+    },
+}
+
+fn one_month() -> u32 {
+    1
+}
+
+/// Роли автора сообщения в чате (из IRC-тегов Twitch).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewerRoles {
+    pub broadcaster: bool,
+    pub moderator: bool,
+    pub vip: bool,
+    pub subscriber: bool,
+}
+
+/// Минимальная роль, нужная для команды. Порядок вариантов — по возрастанию прав.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    #[default]
+    Everyone,
+    Subscriber,
+    Vip,
+    Moderator,
+    Broadcaster,
+}
+
+impl ViewerRoles {
+    pub fn level(&self) -> Permission {
+        if self.broadcaster {
+            Permission::Broadcaster
+        } else if self.moderator {
+            Permission::Moderator
+        } else if self.vip {
+            Permission::Vip
+        } else if self.subscriber {
+            Permission::Subscriber
+        } else {
+            Permission::Everyone
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamEventKind {
+    Chat,
+    Follow,
+    Sub,
+    Bits,
+    Donation,
+    Raid,
+    Redemption,
+}
+
+impl StreamEvent {
+    pub fn kind(&self) -> StreamEventKind {
+        match self {
+            StreamEvent::Chat { .. } => StreamEventKind::Chat,
+            StreamEvent::Follow { .. } => StreamEventKind::Follow,
+            StreamEvent::Sub { .. } => StreamEventKind::Sub,
+            StreamEvent::Bits { .. } => StreamEventKind::Bits,
+            StreamEvent::Donation { .. } => StreamEventKind::Donation,
+            StreamEvent::Raid { .. } => StreamEventKind::Raid,
+            StreamEvent::Redemption { .. } => StreamEventKind::Redemption,
+        }
+    }
+
+    pub fn user(&self) -> &str {
+        match self {
+            StreamEvent::Chat { user, .. }
+            | StreamEvent::Follow { user }
+            | StreamEvent::Sub { user, .. }
+            | StreamEvent::Bits { user, .. }
+            | StreamEvent::Donation { user, .. }
+            | StreamEvent::Raid { user, .. }
+            | StreamEvent::Redemption { user, .. } => user,
+        }
+    }
+
+    /// Роли автора; для событий не из чата — без ролей.
+    pub fn roles(&self) -> ViewerRoles {
+        match self {
+            StreamEvent::Chat { roles, .. } => *roles,
+            _ => ViewerRoles::default(),
+        }
+    }
+
+    /// Числовая «сила» события: биты, сумма доната, зрители рейда, месяцы подписки.
+    pub fn amount(&self) -> Option<f32> {
+        match self {
+            StreamEvent::Sub { months, .. } => Some(*months as f32),
+            StreamEvent::Bits { amount, .. } => Some(*amount as f32),
+            StreamEvent::Donation { amount, .. } => Some(*amount),
+            StreamEvent::Raid { viewers, .. } => Some(*viewers as f32),
+            _ => None,
+        }
+    }
+
+    /// Аргумент команды: текст после первого слова в чате или сообщении с битами,
+    /// ввод зрителя для награды.
+    pub fn argument(&self) -> Option<&str> {
+        let text = match self {
+            StreamEvent::Chat { text, .. } | StreamEvent::Bits { message: Some(text), .. } => {
+                text.trim().split_once(char::is_whitespace)?.1
+            }
+            StreamEvent::Redemption { input: Some(input), .. } => input,
+            _ => return None,
+        };
+        Some(text.trim()).filter(|t| !t.is_empty())
+    }
+
+    /// Первое слово чата или сообщения с битами — кандидат в команды.
+    fn command_word(&self) -> Option<&str> {
+        match self {
+            StreamEvent::Chat { text, .. } | StreamEvent::Bits { message: Some(text), .. } => {
+                text.split_whitespace().next()
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Источник событий стрима: Twitch IRC, TCP-команды, вебхуки, файл-сценарий…
+///
+/// `poll` вызывается раз в кадр из основного потока и не должен блокироваться.
+/// `time` — игровое время кадра, по нему идут источники с расписанием.
+pub trait StreamEventSource: Send + Sync + 'static {
+    fn name(&self) -> &str;
+    fn poll(&mut self, time: &Time, out: &mut Vec<StreamEvent>);
+
+    /// Ответ в чат; источники, которые не умеют писать, его игнорируют.
+    fn reply(&mut self, _text: &str) {}
+}
+
+/// Сообщение, которое игра хочет отправить в чат стрима.
+#[derive(Event, Debug, Clone)]
+pub struct ChatReply(pub String);
+
+#[derive(Resource, Default)]
+pub struct StreamSources {
+    sources: Vec<Box<dyn StreamEventSource>>,
+}
+
+impl StreamSources {
+    pub fn add(&mut self, source: impl StreamEventSource) {
+        info!("Stream event source registered: {}", source.name());
+        self.sources.push(Box::new(source));
+    }
+}
+
+/// Источник для фоновых потоков: они пишут в [`Sender`], игра забирает события в `poll`.
+pub struct ChannelSource {
+    name: String,
+    rx: Mutex<Receiver<StreamEvent>>,
+}
+
+impl ChannelSource {
+    pub fn new(name: impl Into<String>) -> (Sender<StreamEvent>, Self) {
+        let (tx, rx) = mpsc::channel();
+        (tx, Self { name: name.into(), rx: Mutex::new(rx) })
+    }
+}
+
+impl StreamEventSource for ChannelSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn poll(&mut self, _time: &Time, out: &mut Vec<StreamEvent>) {
+        if let Ok(rx) = self.rx.get_mut() {
+            out.extend(rx.try_iter());
+        }
+    }
+}
+
+fn poll_stream_sources(
+    mut sources: ResMut<StreamSources>,
+    time: Res<Time>,
+    mut stream_ev: EventWriter<StreamEvent>,
+    mut buffer: Local<Vec<StreamEvent>>,
+) {
+    for source in &mut sources.sources {
+        source.poll(&time, &mut buffer);
+        for ev in buffer.drain(..) {
+            debug!("Stream event from {}: {:?}", source.name(), ev);
+            stream_ev.write(ev);
+        }
+    }
+}
+
+fn deliver_chat_replies(mut sources: ResMut<StreamSources>, mut replies: EventReader<ChatReply>) {
+    for ChatReply(text) in replies.read() {
+        info!("Chat reply: {}", text);
+        for source in &mut sources.sources {
+            source.reply(text);
+        }
+    }
+}
+
+// region reactions
+
+/// Правило «событие стрима → действие в игре».
+///
+/// На одно событие срабатывают все подходящие правила, но правило с `command` точнее общего:
+/// если событие подошло под правило с командой, правила без `command` его пропускают. Так
+/// `!sponsor` за 500 бит спонсирует башню и не вызывает заодно врагов за 100 бит.
+#[derive(Debug, Clone, Deserialize)]
+pub struct StreamReaction {
+    pub on: StreamEventKind,
+    /// Минимальная сила события, см. [`StreamEvent::amount`].
+    #[serde(default)]
+    pub min_amount: Option<f32>,
+    /// Название (или id из IRC) награды за баллы канала.
+    #[serde(default)]
+    pub reward: Option<String>,
+    /// Чат-команда, например `!spawn`; для битов — первое слово сообщения.
+    #[serde(default)]
+    pub command: Option<String>,
+    /// Минимальная роль зрителя, см. [`ViewerRoles`].
+    #[serde(default)]
+    pub permission: Permission,
+    pub action: GameAction,
+}
+
+impl StreamReaction {
+    pub fn matches(&self, ev: &StreamEvent) -> bool {
+        if ev.kind() != self.on {
+            return false;
+        }
+        if let Some(min) = self.min_amount
+            && ev.amount().is_none_or(|a| a < min)
+        {
+            return false;
+        }
+        if let Some(expected) = &self.reward {
+            match ev {
+                StreamEvent::Redemption { reward, .. } if reward.eq_ignore_ascii_case(expected) => {}
+                _ => return false,
+            }
+        }
+        if let Some(expected) = &self.command
+            && !ev.command_word().is_some_and(|w| w.eq_ignore_ascii_case(expected))
+        {
+            return false;
+        }
+        true
+    }
+}
+
+#[derive(serde::Deserialize, Asset, TypePath, Clone)]
+pub struct StreamReactionsFile {
+    pub reactions: Vec<StreamReaction>,
+}
+
+impl StreamReactionsFile {
+    /// Правила, которые срабатывают на событие, см. [`StreamReaction`].
+    pub fn reactions_for<'a>(&'a self, ev: &StreamEvent) -> Vec<&'a StreamReaction> {
+        let matching: Vec<_> = self.reactions.iter().filter(|r| r.matches(ev)).collect();
+        let by_command = matching.iter().any(|r| r.command.is_some());
+        matching.into_iter().filter(|r| !by_command || r.command.is_some()).collect()
+    }
+}
+
+const STREAM_REACTIONS: &str = "data/stream_reactions.json";
+
+#[derive(Resource)]
+struct StreamReactionsHandle(Handle<StreamReactionsFile>);
+
+fn load_stream_reactions(mut commands: Commands, asset_server: Res<AssetServer>, mut tracker: ResMut<LoadingTracker>) {
+    let path = data_file(STREAM_REACTIONS);
+    let handle: Handle<StreamReactionsFile> = asset_server.load(path.clone());
+    tracker.track(path, handle.clone());
+    commands.insert_resource(StreamReactionsHandle(handle));
+}
+
+fn apply_stream_reactions(
+    mut stream_ev: EventReader<StreamEvent>,
+    handle: Res<StreamReactionsHandle>,
+    files: Res<Assets<StreamReactionsFile>>,
+    mut moderation: ResMut<Moderation>,
+    mut rejected: EventWriter<CommandRejected>,
+    time: Res<Time<Real>>,
+    mut queue: ResMut<CommandQueue>,
+) {
+    let Some(file) = files.get(&handle.0) else {
+        stream_ev.clear();
+        return;
+    };
+
+    for ev in stream_ev.read() {
+        for reaction in file.reactions_for(ev) {
+            let now = time.elapsed_secs_f64();
+            let text = match ev {
+                StreamEvent::Chat { text, .. } => Some(text.clone()),
+                _ => None,
+            };
+            if let Err(reason) = moderation.check(ev, reaction.permission, now) {
+                rejected.write(CommandRejected { user: ev.user().to_string(), kind: ev.kind(), text, reason });
+                continue;
+            }
+            let action = reaction.action.with_argument(ev.argument());
+            queue.push(ev.user(), ev.kind(), text, action, now);
+        }
+    }
+}
+
+// endregion
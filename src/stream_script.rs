@@ -0,0 +1,148 @@
+use crate::AppState;
+use crate::cli::arg_value;
+use crate::stream_event::{StreamEvent, StreamEventSource, StreamSources};
+use anyhow::Context;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Проигрывание и запись событий стрима в JSONL без выхода в эфир.
+///
+/// ```text
+/// {"t": 0.5, "event": {"type": "chat", "user": "viewer1", "text": "!wave"}}
+/// {"t": 3.0, "event": {"type": "raid", "user": "friend", "viewers": 120}}
+/// ```
+///
+/// `t` — секунды игрового времени с момента входа в игру.
+/// Запуск: `--stream-script script.jsonl` и/или `--record-stream out.jsonl`.
+pub struct StreamScriptPlugin;
+
+impl Plugin for StreamScriptPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StreamScriptConfig>()
+            .add_systems(OnEnter(AppState::InGame), start_stream_script)
+            .add_systems(Update, record_stream_events.run_if(resource_exists::<StreamRecorder>));
+    }
+}
+
+#[derive(Resource, Clone)]
+pub struct StreamScriptConfig {
+    pub play: Option<PathBuf>,
+    pub record: Option<PathBuf>,
+}
+
+impl Default for StreamScriptConfig {
+    fn default() -> Self {
+        Self {
+            play: arg_value("--stream-script").map(PathBuf::from),
+            record: arg_value("--record-stream").map(PathBuf::from),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptedEvent {
+    pub t: f32,
+    pub event: StreamEvent,
+}
+
+/// Источник, выдающий события из сценария по расписанию игрового времени.
+pub struct ScriptedSource {
+    name: String,
+    events: VecDeque<ScriptedEvent>,
+    elapsed: Duration,
+}
+
+impl ScriptedSource {
+    pub fn from_jsonl(name: impl Into<String>, text: &str) -> anyhow::Result<Self> {
+        let name = name.into();
+        let mut events = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let ev: ScriptedEvent =
+                serde_json::from_str(line).with_context(|| format!("{}:{}", name, i + 1))?;
+            events.push(ev);
+        }
+        events.sort_by(|a, b| a.t.total_cmp(&b.t));
+        Ok(Self { name, events: events.into(), elapsed: Duration::ZERO })
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read {}", path.display()))?;
+        Self::from_jsonl(path.display().to_string(), &text)
+    }
+}
+
+impl StreamEventSource for ScriptedSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn poll(&mut self, time: &Time, out: &mut Vec<StreamEvent>) {
+        self.elapsed += time.delta();
+        let now = self.elapsed.as_secs_f32();
+        while self.events.front().is_some_and(|ev| ev.t <= now) {
+            if let Some(ev) = self.events.pop_front() {
+                out.push(ev.event);
+            }
+        }
+    }
+}
+
+#[derive(Resource)]
+struct StreamRecorder {
+    out: BufWriter<File>,
+    started: Duration,
+}
+
+fn start_stream_script(
+    mut commands: Commands,
+    config: Res<StreamScriptConfig>,
+    mut sources: ResMut<StreamSources>,
+    time: Res<Time>,
+    mut started: Local<bool>,
+) {
+    if *started {
+        return;
+    }
+    *started = true;
+
+    if let Some(path) = &config.play {
+        match ScriptedSource::load(path) {
+            Ok(source) => sources.add(source),
+            Err(e) => error!("Stream script: {:#}", e),
+        }
+    }
+
+    if let Some(path) = &config.record {
+        match File::create(path) {
+            Ok(file) => {
+                info!("Recording stream events to {}", path.display());
+                commands.insert_resource(StreamRecorder { out: BufWriter::new(file), started: time.elapsed() });
+            }
+            Err(e) => error!("Stream recorder: cannot create {}: {}", path.display(), e),
+        }
+    }
+}
+
+fn record_stream_events(
+    mut stream_ev: EventReader<StreamEvent>,
+    mut recorder: ResMut<StreamRecorder>,
+    time: Res<Time>,
+) {
+    let t = (time.elapsed() - recorder.started).as_secs_f32();
+    for ev in stream_ev.read() {
+        let line = ScriptedEvent { t, event: ev.clone() };
+        let Ok(json) = serde_json::to_string(&line) else { continue; };
+        if let Err(e) = writeln!(recorder.out, "{}", json).and_then(|_| recorder.out.flush()) {
+            warn!("Stream recorder: {}", e);
+        }
+    }
+}
@@ -3,131 +3,379 @@ use bevy::prelude::*;
 use bevy::scene::SceneInstanceReady;
 use rand::{Rng};
 use crate::AppState;
-use crate::world::Game;
+use crate::fixed_step::TranslationInterpolation;
+use crate::level::ActiveLevel;
+use crate::localization::{Locale, LocalizedText};
+use crate::rng::GameRng;
+use std::collections::HashMap;
 
 pub struct TargetPlugin;
 
 impl Plugin for TargetPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<Target>()
-            .insert_resource(TargetCatalog::default())
-            .add_systems(Startup, load_assets)
-            .add_observer(play_animation_when_ready)
+            .register_type::<Health>()
+            .register_type::<Speed>()
+            .register_type::<TargetKind>()
+            .register_type::<SpawnedBy>()
+            .register_type::<Boss>()
+            .register_type::<WaveTarget>()
+            .register_type::<Slowed>()
+            .register_type::<PathFollower>()
+            .init_resource::<TargetCatalog>()
+            .init_resource::<AmbientSpawn>()
+            .add_event::<SpawnTargetEvent>()
+            .add_event::<TargetLeaked>()
+            .add_event::<TargetDamaged>()
+            .add_event::<TargetKilled>()
             .add_systems(
-                Update,
+                FixedUpdate,
                 (
                     spawn_targets,
+                    spawn_target_on_event,
                     move_targets,
-                ).run_if(in_state(AppState::InGame)
-            ));
+                    tick_slowdowns,
+                    leak_targets,
+                ).chain().run_if(in_state(AppState::InGame)).run_if(resource_exists::<ActiveLevel>)
+            );
+    }
+}
+
+/// Модели и анимация ходьбы для целей из [`TargetCatalog`].
+pub struct TargetViewPlugin;
+
+impl Plugin for TargetViewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (build_target_models.run_if(resource_changed::<TargetCatalog>), attach_target_visuals).chain())
+            .add_observer(play_animation_when_ready);
     }
 }
 
 #[derive(Reflect, Component, Default)]
 #[reflect(Component)]
+#[require(TranslationInterpolation)]
 pub struct Target;
 
+/// Запрос на спавн цели; `kind: None` — случайный вид из каталога.
+#[derive(Event, Debug, Clone, Default)]
+pub struct SpawnTargetEvent {
+    pub kind: Option<usize>,
+    pub boss: bool,
+    /// Зритель, по чьей команде появилась цель.
+    pub viewer: Option<String>,
+    /// Волна, которой принадлежит цель.
+    pub wave: Option<u32>,
+}
+
+#[derive(Reflect, Component, Debug, Clone)]
+#[reflect(Component)]
+pub struct SpawnedBy(pub String);
+
+#[derive(Reflect, Component, Debug, Clone, Copy, Default)]
+#[reflect(Component)]
+pub struct Boss;
+
+/// Номер волны, из которой пришла цель.
+#[derive(Reflect, Component, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct WaveTarget(pub u32);
+
+/// Куда идёт цель: маршрут `route` из [`ActiveLevel::routes`] и номер следующей точки на нём.
+#[derive(Reflect, Component, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct PathFollower {
+    pub route: usize,
+    pub next: usize,
+}
+
+/// Цель дошла до цели уровня.
+#[derive(Event, Debug, Clone)]
+pub struct TargetLeaked {
+    pub spawned_by: Option<String>,
+    pub wave: Option<u32>,
+}
+
+/// Цель получила урон; `amount` не больше оставшегося здоровья.
+#[derive(Event, Debug, Clone)]
+pub struct TargetDamaged {
+    pub amount: f32,
+    pub spawned_by: Option<String>,
+}
+
+/// Цель убита; `tower` — башня, чей снаряд добил цель.
+#[derive(Event, Debug, Clone)]
+pub struct TargetKilled {
+    pub tower: Option<Entity>,
+    pub wave: Option<u32>,
+    /// Урон последнего попадания сверх оставшегося здоровья.
+    pub overkill: f32,
+}
+
+/// Кроме волн, цели идут и сами по себе: одна случайная раз в столько секунд.
+pub const AMBIENT_SPAWN_SECONDS: f32 = 2.0;
+
+/// Таймер фонового спавна; часть матча — сбрасывается с уровнем и сохраняется.
+#[derive(Resource, Debug, Clone)]
+pub struct AmbientSpawn {
+    pub timer: Timer,
+}
+
+impl Default for AmbientSpawn {
+    fn default() -> Self {
+        Self { timer: Timer::from_seconds(AMBIENT_SPAWN_SECONDS, TimerMode::Repeating) }
+    }
+}
+
+const BOSS_HEALTH_MULT: f32 = 5.0;
+const BOSS_SCALE_MULT: f32 = 1.6;
+const BOSS_SPEED_MULT: f32 = 0.7;
+
 fn spawn_targets(
-    mut commands: Commands,
-    catalog: Res<TargetCatalog>,
     time: Res<Time>,
-    mut game: Query<&mut Game>,
+    mut ambient: ResMut<AmbientSpawn>,
+    mut spawn_ev: EventWriter<SpawnTargetEvent>,
 ) {
-    let mut game = game.single_mut().unwrap();
-    if !game.target_spawn_timer.tick(time.delta()).just_finished() {
+    if !ambient.timer.tick(time.delta()).just_finished() {
         return;
     }
+    spawn_ev.write(SpawnTargetEvent::default());
+}
 
-    if catalog.entries.is_empty() {
+fn spawn_target_on_event(
+    mut commands: Commands,
+    mut spawn_ev: EventReader<SpawnTargetEvent>,
+    catalog: Res<TargetCatalog>,
+    level: Res<ActiveLevel>,
+    mut rng: ResMut<GameRng>,
+) {
+    if catalog.is_empty() {
+        spawn_ev.clear();
         return;
     }
-    let mut rng = rand::rng();
-    let kind = rng.random_range(0..catalog.entries.len());
+    for ev in spawn_ev.read() {
+        let kind = match ev.kind {
+            Some(kind) if kind < catalog.entries.len() => kind,
+            Some(kind) => {
+                warn!("Unknown target kind {}", kind);
+                continue;
+            }
+            None => rng.gameplay.random_range(0..catalog.entries.len()),
+        };
+        // С одним путём случайность не тратится: так реплеи старых карт не расходятся.
+        let route = match level.routes.len() {
+            0 => continue,
+            1 => 0,
+            n => rng.gameplay.random_range(0..n),
+        };
+        let follower = PathFollower { route, next: 1 };
+        let mut target = spawn_target(&mut commands, &catalog, kind, ev.boss, level.routes[route][0], follower);
+        if let Some(viewer) = &ev.viewer {
+            target.insert(SpawnedBy(viewer.clone()));
+        }
+        if let Some(wave) = ev.wave {
+            target.insert(WaveTarget(wave));
+        }
+    }
+}
+
+/// Спавнит цель вида `kind` в точке `pos` пути; вид должен быть в каталоге.
+pub fn spawn_target<'a>(
+    commands: &'a mut Commands,
+    catalog: &TargetCatalog,
+    kind: usize,
+    boss: bool,
+    pos: Vec3,
+    follower: PathFollower,
+) -> EntityCommands<'a> {
     let entry = &catalog.entries[kind];
+    let (health, speed, scale) = if boss {
+        (entry.health * BOSS_HEALTH_MULT, entry.speed * BOSS_SPEED_MULT, entry.scale * BOSS_SCALE_MULT)
+    } else {
+        (entry.health, entry.speed, entry.scale)
+    };
 
-    commands
-        .spawn((
-            Name::new(entry.name),
-            Target,
-            TargetKind(kind),
-            Speed(0.3),
-            Health(300.0),
-            SceneRoot(entry.scene.clone()),
-            Transform::from_xyz(-2.0, 0.0, 2.0)
-                .with_scale(Vec3::splat(entry.spawn_scale))
-                .with_rotation(Quat::from_rotation_y(std::f32::consts::PI * 0.5)),
-        ));
+    let mut target = commands.spawn((
+        Name::new(if boss { format!("{}_Boss", entry.id) } else { entry.id.clone() }),
+        Target,
+        TargetKind(entry.id.clone()),
+        Speed(speed),
+        Health(health),
+        follower,
+        Transform::from_translation(pos)
+            .with_scale(Vec3::splat(scale))
+            .with_rotation(Quat::from_rotation_y(std::f32::consts::PI * 0.5)),
+    ));
+    if boss {
+        target.insert(Boss);
+    }
+    target
 }
 
-fn move_targets(mut q: Query<(&mut Transform, &Speed), With<Target>>, time: Res<Time>) {
-    for (mut t, s) in &mut q {
-        t.translation.x += s.0 * time.delta_secs();
+type Walker = (&'static mut Transform, &'static mut PathFollower, &'static Speed, Option<&'static Slowed>);
+
+/// Ведёт цели по точкам маршрута; остаток шага после точки тратится на следующий отрезок.
+fn move_targets(mut q: Query<Walker, With<Target>>, level: Res<ActiveLevel>, time: Res<Time>) {
+    for (mut t, mut follower, s, slowed) in &mut q {
+        let Some(route) = level.routes.get(follower.route) else { continue; };
+        let factor = slowed.map_or(1.0, |slowed| slowed.factor);
+        let mut step = s.0 * factor * time.delta_secs();
+        while let Some(&point) = route.get(follower.next) {
+            let to = point - t.translation;
+            if to.x != 0.0 || to.z != 0.0 {
+                t.rotation = Quat::from_rotation_y(to.x.atan2(to.z));
+            }
+            let dist = to.length();
+            if dist > step {
+                t.translation += to / dist * step;
+                break;
+            }
+            t.translation = point;
+            step -= dist;
+            follower.next += 1;
+        }
     }
 }
 
-const GLB_TARGET_1: &str = "glb/target_01.glb";
-const GLB_TARGET_2: &str = "glb/target_02.glb";
+fn tick_slowdowns(mut commands: Commands, mut q: Query<(Entity, &mut Slowed)>, time: Res<Time>) {
+    for (e, mut slowed) in &mut q {
+        if slowed.timer.tick(time.delta()).finished() {
+            commands.entity(e).remove::<Slowed>();
+        }
+    }
+}
+
+type LeakCheck = (Entity, &'static PathFollower, Option<&'static SpawnedBy>, Option<&'static WaveTarget>);
+
+fn leak_targets(
+    mut commands: Commands,
+    q: Query<LeakCheck, With<Target>>,
+    level: Res<ActiveLevel>,
+    mut leak_ev: EventWriter<TargetLeaked>,
+) {
+    for (e, follower, spawned_by, wave) in &q {
+        // Путь пропал из уровня при правке файла — считаем, что цель дошла.
+        if level.routes.get(follower.route).is_none_or(|route| follower.next >= route.len()) {
+            commands.entity(e).despawn();
+            leak_ev.write(TargetLeaked { spawned_by: spawned_by.map(|s| s.0.clone()), wave: wave.map(|w| w.0) });
+        }
+    }
+}
+
+/// Вид врага из `data/enemies.json`.
+#[derive(Debug, Clone, Default)]
+pub struct EnemyDef {
+    pub id: String,
+    pub name: LocalizedText,
+    pub description: LocalizedText,
+    pub scene: Handle<Scene>,
+    /// Анимация ходьбы из того же glb.
+    pub walk: Handle<AnimationClip>,
+    pub scale: f32,
+    pub health: f32,
+    pub speed: f32,
+}
 
-#[derive(Clone)]
-struct TargetEntry {
+struct TargetModel {
     scene: Handle<Scene>,
     graph: Handle<AnimationGraph>,
     clip_index: AnimationNodeIndex,
-    spawn_scale: f32,
-    name: &'static str,
 }
 
-#[derive(Component)]
+/// Загруженные модели по id врага, см. [`TargetKind`].
+#[derive(Resource)]
+struct TargetModels(HashMap<String, TargetModel>);
+
+#[derive(Reflect, Component, Debug, Clone, Copy)]
+#[reflect(Component)]
 pub struct Speed(pub f32);
 
-#[derive(Component)]
+#[derive(Reflect, Component, Debug, Clone, Copy)]
+#[reflect(Component)]
 pub struct Health(pub f32);
 
-#[derive(Component)]
-pub struct TargetKind(pub usize);
+/// Замедление от попадания: скорость умножается на `factor`, пока идёт таймер.
+/// Новое замедление заменяет старое.
+#[derive(Reflect, Component, Debug, Clone)]
+#[reflect(Component)]
+pub struct Slowed {
+    pub factor: f32,
+    pub timer: Timer,
+}
 
+impl Slowed {
+    pub fn new(factor: f32, seconds: f32) -> Self {
+        Self { factor, timer: Timer::from_seconds(seconds, TimerMode::Once) }
+    }
+}
+
+/// Id врага из [`TargetCatalog`]: номер в каталоге после перезагрузки файла может сменить хозяина.
+#[derive(Reflect, Component, Debug, Clone)]
+#[reflect(Component)]
+pub struct TargetKind(pub String);
+
+/// Виды врагов в порядке `data/enemies.json` и паков. Пуст, пока файл не загружен;
+/// собирается в [`DataLoadPlugin`](crate::data_load::DataLoadPlugin).
 #[derive(Resource, Default)]
-struct TargetCatalog {
-    entries: Vec<TargetEntry>,
+pub struct TargetCatalog {
+    entries: Vec<EnemyDef>,
 }
 
-const ANIM_WALK_IDX: usize = 6;
+impl TargetCatalog {
+    pub fn new(entries: Vec<EnemyDef>) -> Self {
+        Self { entries }
+    }
 
-fn load_assets(
-    asset_server: Res<AssetServer>,
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.entries.iter().position(|e| e.id.eq_ignore_ascii_case(name))
+    }
+
+    /// Имя врага на языке интерфейса; без перевода — сам `id`.
+    pub fn display_name<'a>(&'a self, kind: usize, locale: &Locale) -> &'a str {
+        let entry = &self.entries[kind];
+        locale.text(&entry.name).unwrap_or(&entry.id)
+    }
+
+    pub fn description<'a>(&'a self, kind: usize, locale: &Locale) -> Option<&'a str> {
+        locale.text(&self.entries[kind].description)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.entries.iter().map(|e| e.id.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Хэндлы моделей грузит `DataLoadPlugin`; здесь из анимаций собираются графы.
+fn build_target_models(
+    mut commands: Commands,
     mut graphs: ResMut<Assets<AnimationGraph>>,
-    mut catalog: ResMut<TargetCatalog>,
+    catalog: Res<TargetCatalog>,
 ) {
-    // Target 1
-    let scene1 = asset_server.load(GltfAssetLabel::Scene(0).from_asset(GLB_TARGET_1));
-    let (graph1, idx1) = AnimationGraph::from_clip(
-        asset_server.load(GltfAssetLabel::Animation(ANIM_WALK_IDX).from_asset(GLB_TARGET_1)),
-    );
-    let graph1 = graphs.add(graph1);
-
-    // Target 2
-    let scene2 = asset_server.load(GltfAssetLabel::Scene(0).from_asset(GLB_TARGET_2));
-    let (graph2, idx2) = AnimationGraph::from_clip(
-        asset_server.load(GltfAssetLabel::Animation(ANIM_WALK_IDX).from_asset(GLB_TARGET_2)),
-    );
-    let graph2 = graphs.add(graph2);
-
-    catalog.entries = vec![
-        TargetEntry {
-            scene: scene1,
-            graph: graph1,
-            clip_index: idx1,
-            spawn_scale: 0.15,
-            name: "Target_1",
-        },
-        TargetEntry {
-            scene: scene2,
-            graph: graph2,
-            clip_index: idx2,
-            spawn_scale: 0.25,
-            name: "Target_2",
-        },
-    ];
+    let models = catalog.entries.iter().map(|entry| {
+        let (graph, clip_index) = AnimationGraph::from_clip(entry.walk.clone());
+        (entry.id.clone(), TargetModel { scene: entry.scene.clone(), graph: graphs.add(graph), clip_index })
+    }).collect();
+    commands.insert_resource(TargetModels(models));
+}
+
+fn attach_target_visuals(
+    mut commands: Commands,
+    targets: Query<(Entity, &TargetKind), Added<Target>>,
+    models: Option<Res<TargetModels>>,
+) {
+    let Some(models) = models else { return; };
+    for (e, kind) in &targets {
+        if let Some(model) = models.0.get(&kind.0) {
+            commands.entity(e).try_insert(SceneRoot(model.scene.clone()));
+        }
+    }
 }
 
 fn play_animation_when_ready(
@@ -136,11 +384,11 @@ fn play_animation_when_ready(
     children: Query<&Children>,
     mut players: Query<&mut AnimationPlayer>,
     graph_handle: Query<&AnimationGraphHandle>,
-    catalog: Res<TargetCatalog>,
+    models: Res<TargetModels>,
     kind_q: Query<&TargetKind>,
 ) {
     let Ok(kind) = kind_q.get(trigger.target()) else { return; };
-    let Some(entry) = catalog.entries.get(kind.0) else { return; };
+    let Some(entry) = models.0.get(&kind.0) else { return; };
 
     for child in children.iter_descendants(trigger.target()) {
         if let Ok(mut player) = players.get_mut(child) {
@@ -1,21 +1,57 @@
 use crate::{AppState};
-use crate::projectile::Projectile;
-use crate::target::Target;
+use crate::economy::Gold;
+use crate::game_action::{GameAction, GameActions};
+use crate::localization::{Locale, LocalizedText};
+use crate::projectile::{AIM_HEIGHT, Projectile, ProjectileDef};
+use crate::target::{Target, TargetKilled};
 use bevy::math::{FloatOrd, Vec3};
 use bevy::prelude::*;
 use std::collections::HashMap;
+use std::time::Duration;
 use bevy::audio::Volume;
 use bevy::color::palettes::css::YELLOW;
 use bevy::scene::SceneInstanceReady;
 use bevy_inspector_egui::{bevy_egui, egui};
+use serde::{Deserialize, Serialize};
 
 impl Plugin for TowerPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<Tower>()
-            .add_systems(Update, spawn_some_towers.run_if(resource_added::<TowerDB>))
-            .add_systems(Update, spawn_projectiles.run_if(in_state(AppState::InGame)))
-            .init_resource::<SelectedTower>()
+            .register_type::<TowerOwner>()
+            .register_type::<TowerId>()
+            .register_type::<TowerStats>()
+            .init_resource::<NextTowerId>()
+            .add_observer(assign_tower_id)
+            .add_event::<SellTowerEvent>()
+            .add_event::<UpgradeTowerEvent>()
+            .add_systems(FixedUpdate, (upgrade_towers, sell_towers).chain().run_if(in_state(AppState::InGame)))
+            .add_event::<PlaceTowerEvent>()
+            .add_event::<SponsorTowerEvent>()
+            .init_resource::<Sponsorships>()
+            .add_systems(Update, (queue_sponsorships, count_tower_kills))
+            .add_systems(FixedUpdate, place_tower_on_event.run_if(resource_exists::<TowerDB>))
+            .add_systems(Update, refresh_tower_stats.run_if(resource_exists_and_changed::<TowerDB>))
+            .add_event::<FreezeTowersEvent>()
+            .init_resource::<TowerFreeze>()
+            .add_systems(FixedUpdate, (freeze_towers, tick_tower_freeze).chain().run_if(in_state(AppState::InGame)))
+            .add_systems(
+                FixedUpdate,
+                spawn_projectiles
+                    .run_if(in_state(AppState::InGame))
+                    .run_if(towers_not_frozen),
+            );
+    }
+}
+
+/// Модели башен и снарядов, звуки выстрелов, выделение башни мышью и её панель.
+/// Сама симуляция в [`TowerPlugin`] работает с «голыми» сущностями без сцен.
+pub struct TowerViewPlugin;
+
+impl Plugin for TowerViewPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SelectedTower>()
             .init_resource::<TowerClickFlag>()
+            .add_systems(Update, (attach_tower_visuals, attach_projectile_visuals, refresh_tower_scene))
             .add_systems(Startup, setup_selection_materials)
             .add_systems(Update, apply_tower_selection.run_if(resource_changed::<SelectedTower>))
             .add_systems(
@@ -34,16 +70,36 @@ pub struct TowerPlugin;
 #[reflect(Component)]
 pub struct Tower {
     pub shooting_timer: Timer,
+    pub kills: u32,
+    pub level: u32,
+    /// Золото, вложенное в улучшения; половина возвращается при продаже.
+    pub invested: u32,
 }
 
-#[derive(Component, Clone)]
+/// Номер башни, стабильный между запусками: по нему на башни ссылаются реплеи,
+/// сохранения и внешние команды.
+#[derive(Reflect, Component, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[reflect(Component)]
+pub struct TowerId(pub u32);
+
+#[derive(Resource, Default)]
+pub struct NextTowerId(pub u32);
+
+/// Зритель, спонсировавший башню.
+#[derive(Reflect, Component, Clone, Debug)]
+#[reflect(Component)]
+pub struct TowerOwner(pub String);
+
+#[derive(Reflect, Component, Clone)]
+#[reflect(Component)]
 pub struct TowerStats {
+    /// Id описания в `TowerDB`.
+    pub def_id: String,
     pub damage: f32,
+    pub scene: Handle<Scene>,
     pub projectile_offset: Vec3,
     pub range_sq: f32,
-    pub projectile_speed: f32,
-    pub projectile_scale: f32,
-    pub projectile_scene: Handle<Scene>,
+    pub projectile: ProjectileDef,
     pub shot_sound: Handle<AudioSource>,
     pub shot_volume: f32
 }
@@ -52,10 +108,8 @@ pub struct TowerStats {
 pub struct TowerBundle {
     pub tower: Tower,
     pub stats: TowerStats,
-    pub scene: SceneRoot,
     pub transform: Transform,
     pub name: Name,
-    pub pickable: Pickable
 }
 
 #[derive(Resource)]
@@ -69,119 +123,332 @@ pub struct TowerDefComponent;
 #[derive(Clone)]
 pub struct TowerDef {
     pub id: String,
+    pub name: LocalizedText,
+    pub description: LocalizedText,
     pub damage: f32,
     pub scene: Handle<Scene>,
-    pub projectile_scene: Handle<Scene>,
     pub fire_interval: f32,
     pub range: f32,
-    pub projectile_speed: f32,
-    pub projectile_scale: f32,
+    pub projectile: ProjectileDef,
     pub offset: Vec3,
     pub shot_sound: Handle<AudioSource>,
     pub shot_volume: f32
 }
-// endregion
 
-// region systems
-pub fn spawn_some_towers(mut commands: Commands, db: Res<TowerDB>) {
-    let basic = db.defs.get("basic").unwrap();
-    let sniper = db.defs.get("sniper").unwrap();
-
-    spawn_tower_of(
-        &mut commands,
-        basic,
-        Vec3::new(0.0, 0.0, 0.0),
-    );
-    spawn_tower_of(
-        &mut commands,
-        sniper,
-        Vec3::new(3.0, 0.0, 0.0),
-    );
+impl TowerDef {
+    /// Название на языке интерфейса; без перевода — `id`.
+    pub fn display_name<'a>(&'a self, locale: &Locale) -> &'a str {
+        locale.text(&self.name).unwrap_or(&self.id)
+    }
+}
+
+/// Поставить башню из `TowerDB` по id — общий путь для UI и внешних команд.
+#[derive(Event, Debug, Clone)]
+pub struct PlaceTowerEvent {
+    pub id: String,
+    pub pos: Vec3,
+    pub owner: Option<String>,
+}
+
+/// Зритель оплатил башню; стример ставит её сам из панели строительства.
+#[derive(Event, Debug, Clone)]
+pub struct SponsorTowerEvent {
+    pub viewer: String,
+    pub tower: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sponsorship {
+    pub viewer: String,
+    pub tower: String,
+}
+
+/// Спонсорские башни, которые ещё не поставлены.
+#[derive(Resource, Default)]
+pub struct Sponsorships {
+    pub pending: Vec<Sponsorship>,
 }
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SellTowerEvent {
+    pub tower: Entity,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct UpgradeTowerEvent {
+    pub tower: Entity,
+}
+
+pub const MAX_TOWER_LEVEL: u32 = 3;
+pub const UPGRADE_COST: u32 = 50;
+const UPGRADE_DAMAGE_MULT: f32 = 1.25;
+const UPGRADE_RANGE_MULT: f32 = 1.1;
+
+/// Остановить стрельбу всех башен на `seconds` секунд.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct FreezeTowersEvent {
+    pub seconds: f32,
+}
+
+#[derive(Resource, Default)]
+pub struct TowerFreeze(pub Option<Timer>);
+// endregion
 
+// region systems
 pub fn spawn_tower_of(commands: &mut Commands,
                   def: &TowerDef,
-                  pos: Vec3) {
+                  pos: Vec3,
+                  owner: Option<String>) {
 
-    commands.spawn(TowerBundle {
+    let mut tower = commands.spawn(tower_bundle(def, pos));
+    if let Some(owner) = owner {
+        tower.insert(TowerOwner(owner));
+    }
+}
+
+/// Башня первого уровня по описанию; [`TowerId`] выдаётся при спавне, если его нет в бандле.
+pub fn tower_bundle(def: &TowerDef, pos: Vec3) -> TowerBundle {
+    TowerBundle {
         tower: Tower {
             shooting_timer: Timer::from_seconds(def.fire_interval, TimerMode::Repeating),
+            kills: 0,
+            level: 1,
+            invested: 0,
         },
-        stats: TowerStats {
-            damage: def.damage,
-            projectile_offset: def.offset,
-            range_sq: def.range * def.range,
-            projectile_speed: def.projectile_speed,
-            projectile_scale: def.projectile_scale,
-            projectile_scene: def.projectile_scene.clone(),
-            shot_sound: def.shot_sound.clone(),
-            shot_volume: def.shot_volume
-        },
-        scene: SceneRoot(def.scene.clone()),
+        stats: tower_stats(def, 1),
         transform: Transform::from_translation(pos),
         name: Name::new(def.id.clone()),
-        pickable: Pickable::default(),
-    }).observe(attach_tower_clickables);
+    }
+}
+
+/// Статы башни уровня `level` по описанию.
+pub fn tower_stats(def: &TowerDef, level: u32) -> TowerStats {
+    let mut stats = TowerStats {
+        def_id: def.id.clone(),
+        damage: def.damage,
+        scene: def.scene.clone(),
+        projectile_offset: def.offset,
+        range_sq: def.range * def.range,
+        projectile: def.projectile.clone(),
+        shot_sound: def.shot_sound.clone(),
+        shot_volume: def.shot_volume
+    };
+    for _ in 1..level {
+        upgrade_stats(&mut stats);
+    }
+    stats
+}
+
+/// Статы башни после ещё одного улучшения.
+pub fn upgrade_stats(stats: &mut TowerStats) {
+    stats.damage *= UPGRADE_DAMAGE_MULT;
+    stats.range_sq *= UPGRADE_RANGE_MULT * UPGRADE_RANGE_MULT;
+}
+
+fn place_tower_on_event(
+    mut commands: Commands,
+    mut ev: EventReader<PlaceTowerEvent>,
+    db: Res<TowerDB>,
+    mut sponsorships: ResMut<Sponsorships>,
+) {
+    for PlaceTowerEvent { id, pos, owner } in ev.read() {
+        let Some(def) = db.defs.get(id) else {
+            warn!("Unknown tower id: {}", id);
+            continue;
+        };
+        if let Some(viewer) = owner
+            && let Some(i) = sponsorships.pending.iter().position(|s| s.viewer == *viewer && s.tower == *id)
+        {
+            sponsorships.pending.remove(i);
+        }
+        spawn_tower_of(&mut commands, def, *pos, owner.clone());
+    }
+}
+
+/// `TowerDB` пересобрана — башни получают новые статы, сохраняя место, уровень и убийства.
+fn refresh_tower_stats(db: Res<TowerDB>, mut towers: Query<(&mut Tower, &mut TowerStats, &TowerId)>) {
+    for (mut tower, mut stats, id) in &mut towers {
+        let Some(def) = db.defs.get(&stats.def_id) else {
+            warn!("Tower #{}: '{}' is no longer in TowerDB, keeping old stats", id.0, stats.def_id);
+            continue;
+        };
+        *stats = tower_stats(def, tower.level);
+        let elapsed = tower.shooting_timer.elapsed().min(Duration::from_secs_f32(def.fire_interval));
+        tower.shooting_timer = Timer::from_seconds(def.fire_interval, TimerMode::Repeating);
+        tower.shooting_timer.set_elapsed(elapsed);
+    }
+}
+
+fn assign_tower_id(
+    trigger: Trigger<OnAdd, Tower>,
+    mut commands: Commands,
+    mut next: ResMut<NextTowerId>,
+    with_id: Query<(), With<TowerId>>,
+) {
+    // Башни из сохранения приходят со своим номером.
+    if with_id.contains(trigger.target()) {
+        return;
+    }
+    commands.entity(trigger.target()).insert(TowerId(next.0));
+    next.0 += 1;
+}
+
+fn upgrade_towers(
+    mut ev: EventReader<UpgradeTowerEvent>,
+    mut towers: Query<(&mut Tower, &mut TowerStats, &TowerId)>,
+    mut gold: ResMut<Gold>,
+) {
+    for UpgradeTowerEvent { tower } in ev.read() {
+        let Ok((mut tower, mut stats, id)) = towers.get_mut(*tower) else { continue; };
+        if tower.level >= MAX_TOWER_LEVEL {
+            warn!("Tower #{} is already at max level", id.0);
+            continue;
+        }
+        if gold.0 < UPGRADE_COST {
+            warn!("Not enough gold to upgrade tower #{}: {} < {}", id.0, gold.0, UPGRADE_COST);
+            continue;
+        }
+        gold.0 -= UPGRADE_COST;
+        tower.level += 1;
+        tower.invested += UPGRADE_COST;
+        upgrade_stats(&mut stats);
+        info!("Tower #{} upgraded to level {}", id.0, tower.level);
+    }
+}
+
+fn sell_towers(
+    mut commands: Commands,
+    mut ev: EventReader<SellTowerEvent>,
+    towers: Query<(&Tower, &TowerId)>,
+    mut gold: ResMut<Gold>,
+    mut selected: Option<ResMut<SelectedTower>>,
+) {
+    for SellTowerEvent { tower: e } in ev.read() {
+        let Ok((tower, id)) = towers.get(*e) else { continue; };
+        let refund = tower.invested / 2;
+        gold.0 = gold.0.saturating_add(refund);
+        commands.entity(*e).despawn();
+        if let Some(selected) = selected.as_mut()
+            && selected.0 == Some(*e)
+        {
+            selected.0 = None;
+        }
+        info!("Tower #{} sold for {} gold", id.0, refund);
+    }
+}
+
+fn count_tower_kills(mut ev: EventReader<TargetKilled>, mut towers: Query<&mut Tower>) {
+    for e in ev.read() {
+        if let Some(mut tower) = e.tower.and_then(|t| towers.get_mut(t).ok()) {
+            tower.kills += 1;
+        }
+    }
+}
+
+fn queue_sponsorships(mut ev: EventReader<SponsorTowerEvent>, mut sponsorships: ResMut<Sponsorships>) {
+    for SponsorTowerEvent { viewer, tower } in ev.read() {
+        info!("{} sponsored a {} tower", viewer, tower);
+        sponsorships.pending.push(Sponsorship { viewer: viewer.clone(), tower: tower.clone() });
+    }
+}
+
+fn freeze_towers(mut ev: EventReader<FreezeTowersEvent>, mut freeze: ResMut<TowerFreeze>) {
+    for FreezeTowersEvent { seconds } in ev.read() {
+        let left = freeze.0.as_ref().map_or(0.0, |t| t.remaining_secs());
+        freeze.0 = Some(Timer::from_seconds(left.max(*seconds), TimerMode::Once));
+        info!("Towers frozen for {:.1}s", left.max(*seconds));
+    }
+}
+
+fn tick_tower_freeze(mut freeze: ResMut<TowerFreeze>, time: Res<Time>) {
+    let Some(timer) = freeze.0.as_mut() else { return; };
+    if timer.tick(time.delta()).finished() {
+        freeze.0 = None;
+        info!("Towers unfrozen");
+    }
+}
+
+fn towers_not_frozen(freeze: Res<TowerFreeze>) -> bool {
+    freeze.0.is_none()
 }
 
 fn spawn_projectiles(
     mut commands: Commands,
-    mut towers: Query<(&GlobalTransform, &mut Tower, &TowerStats)>,
-    targets: Query<&GlobalTransform, With<Target>>,
+    mut towers: Query<(Entity, &Transform, &mut Tower, &TowerStats)>,
+    targets: Query<(Entity, &Transform), With<Target>>,
     time: Res<Time>,
 ) {
-    for (gt, mut tower, stats) in &mut towers {
+    for (tower_entity, tower_transform, mut tower, stats) in &mut towers {
         tower.shooting_timer.tick(time.delta());
         if !tower.shooting_timer.just_finished() {
             continue;
         }
 
-        let muzzle = gt.translation() + stats.projectile_offset;
+        let muzzle = tower_transform.translation + stats.projectile_offset;
 
-        let maybe_dir = targets
+        let nearest = targets
             .iter()
-            .filter_map(|tgt| {
-                let to = tgt.translation() + Vec3::Y * 0.3 - muzzle;
+            .filter_map(|(target, tgt)| {
+                let to = tgt.translation + Vec3::Y * AIM_HEIGHT - muzzle;
                 let d2 = to.length_squared();
                 if d2 <= stats.range_sq {
-                    Some((d2, to))
+                    Some((d2, to, target))
                 } else {
                     None
                 }
             })
-            .min_by_key(|(d2, _)| FloatOrd(*d2))
-            .map(|(_, to)| to);
+            .min_by_key(|(d2, ..)| FloatOrd(*d2));
 
-        if let Some(dir) = maybe_dir {
-            let dir_norm = dir.normalize();
-            let rot = Quat::from_rotation_arc(-Vec3::Z, dir_norm);
+        if let Some((_, to, target)) = nearest {
+            let rot = Quat::from_rotation_arc(-Vec3::Z, to.normalize());
 
             commands.spawn((
-                SceneRoot(stats.projectile_scene.clone()),
                 Transform::from_translation(muzzle)
                     .with_rotation(rot)
-                    .with_scale(Vec3::splat(stats.projectile_scale)),
-                Projectile {
-                    speed: stats.projectile_speed,
-                    direction: dir_norm,
-                    life_timer: Timer::from_seconds(2.0, TimerMode::Once),
-                    damage: stats.damage,
-                },
+                    .with_scale(Vec3::splat(stats.projectile.scale)),
+                Projectile::launch(&stats.projectile, to, stats.damage, tower_entity, target),
                 Name::new("Projectile"),
             ));
-            commands.spawn((
-                Name::new("shot_sound"),
-                Transform::from_translation(muzzle),
-                GlobalTransform::default(),
-                AudioPlayer(stats.shot_sound.clone()),
-                PlaybackSettings::DESPAWN
-                    .with_spatial(true)
-                    .with_volume(Volume::Linear(stats.shot_volume))
-            ));
         }
     }
 }
 
+fn attach_tower_visuals(mut commands: Commands, towers: Query<(Entity, &TowerStats), Added<Tower>>) {
+    for (e, stats) in &towers {
+        commands.entity(e)
+            .try_insert((SceneRoot(stats.scene.clone()), Pickable::default()))
+            .observe(attach_tower_clickables);
+    }
+}
+
+/// Модель башни поменялась в `towers.json`.
+fn refresh_tower_scene(mut towers: Query<(&TowerStats, &mut SceneRoot), Changed<TowerStats>>) {
+    for (stats, mut scene) in &mut towers {
+        if scene.0 != stats.scene {
+            scene.0 = stats.scene.clone();
+        }
+    }
+}
+
+fn attach_projectile_visuals(
+    mut commands: Commands,
+    projectiles: Query<(Entity, &Projectile, &Transform), Added<Projectile>>,
+    towers: Query<&TowerStats>,
+) {
+    for (e, projectile, transform) in &projectiles {
+        commands.entity(e).try_insert(SceneRoot(projectile.def.scene.clone()));
+        let Some(stats) = projectile.source.and_then(|t| towers.get(t).ok()) else { continue; };
+        commands.spawn((
+            Name::new("shot_sound"),
+            Transform::from_translation(transform.translation),
+            GlobalTransform::default(),
+            AudioPlayer(stats.shot_sound.clone()),
+            PlaybackSettings::DESPAWN
+                .with_spatial(true)
+                .with_volume(Volume::Linear(stats.shot_volume))
+        ));
+    }
+}
+
 #[derive(Component)]
 struct TowerRoot(Entity);
 
@@ -285,10 +552,20 @@ fn tower_selected(selected: Res<SelectedTower>) -> bool {
     selected.0.is_some()
 }
 
+type TowerInfo = (
+    &'static TowerStats,
+    &'static Tower,
+    &'static TowerId,
+    Option<&'static Name>,
+    Option<&'static TowerOwner>,
+);
+
 fn ui_selected_tower_panel(
     mut egui_ctx: bevy_egui::EguiContexts,
     selected: Res<SelectedTower>,
-    stats_q: Query<(&TowerStats, Option<&Name>)>,
+    stats_q: Query<TowerInfo>,
+    (tower_db, locale): (Res<TowerDB>, Res<Locale>),
+    mut actions: GameActions,
 ) {
     let Some(entity) = selected.0 else { return; };
 
@@ -304,20 +581,47 @@ fn ui_selected_tower_panel(
                 .stroke(ui.visuals().widgets.noninteractive.bg_stroke);
 
             egui::Frame::show(frame, ui, |ui| {
-                ui.heading("Башня");
+                ui.heading(locale.t("tower.title"));
                 ui.separator();
 
-                if let Ok((stats, name)) = stats_q.get(entity) {
+                if let Ok((stats, tower, id, name, owner)) = stats_q.get(entity) {
+                    // Имя из базы на языке интерфейса; башни не из базы — по имени сущности.
+                    let def = tower_db.defs.get(&stats.def_id);
+                    let name = def.map(|d| d.display_name(&locale)).or(name.map(Name::as_str));
                     if let Some(name) = name {
-                        ui.label(format!("ID: {}", name.as_str()));
+                        ui.label(locale.fmt("tower.name", &[("name", &name), ("id", &id.0)]));
+                    }
+                    if let Some(description) = def.and_then(|d| locale.text(&d.description)) {
+                        ui.label(description);
+                    }
+                    ui.label(locale.fmt("tower.level", &[("level", &tower.level), ("max", &MAX_TOWER_LEVEL)]));
+                    if let Some(owner) = owner {
+                        ui.label(locale.fmt("tower.sponsor", &[("viewer", &owner.0)]));
                     }
-                    ui.label(format!("Damage: {:.1}", stats.damage));
-                    ui.label(format!("Range: {:.1}", stats.range_sq.sqrt()));
-                    ui.label(format!("Projectile speed: {:.1}", stats.projectile_speed));
-                    ui.label(format!("Proj size: {:.2}", stats.projectile_scale));
-                    ui.label(format!("Volume: {:.1}", stats.shot_volume));
+                    ui.label(locale.fmt("tower.kills", &[("kills", &tower.kills)]));
+                    ui.label(locale.fmt("tower.damage", &[("damage", &format!("{:.1}", stats.damage))]));
+                    ui.label(locale.fmt("tower.range", &[("range", &format!("{:.1}", stats.range_sq.sqrt()))]));
+                    ui.label(locale.fmt("tower.projectile", &[("projectile", &stats.projectile.id)]));
+                    ui.label(locale.fmt("tower.projectile_speed", &[("speed", &format!("{:.1}", stats.projectile.speed))]));
+                    ui.label(locale.fmt("tower.projectile_size", &[("size", &format!("{:.2}", stats.projectile.scale))]));
+                    ui.label(locale.fmt("tower.volume", &[("volume", &format!("{:.1}", stats.shot_volume))]));
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        let can_upgrade = tower.level < MAX_TOWER_LEVEL;
+                        let upgrade = locale.fmt("tower.upgrade", &[("cost", &UPGRADE_COST)]);
+                        if ui.add_enabled(can_upgrade, egui::Button::new(upgrade)).clicked()
+                            && let Err(e) = actions.apply(&GameAction::UpgradeTower { tower: id.0 })
+                        {
+                            warn!("Upgrade failed: {}", e);
+                        }
+                        if ui.button(locale.fmt("tower.sell", &[("gold", &(tower.invested / 2))])).clicked()
+                            && let Err(e) = actions.apply(&GameAction::SellTower { tower: id.0 })
+                        {
+                            warn!("Sell failed: {}", e);
+                        }
+                    });
                 } else {
-                    ui.label("Нет данных по выбранной башне");
+                    ui.label(locale.t("tower.no_data"));
                 }
             });
         });
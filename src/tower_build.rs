@@ -1,5 +1,10 @@
 use crate::PlayerState;
-use crate::tower::{TowerDB, TowerDef, spawn_tower_of, Tower, TowerStats, TowerBundle};
+use crate::economy::{Gold, Lives};
+use crate::game_action::{GameAction, GameActions};
+use crate::localization::Locale;
+use crate::target::TargetCatalog;
+use crate::tower::{Sponsorships, TowerDB, TowerDef};
+use crate::wave::{WaveSchedule, WaveState};
 use bevy::diagnostic::FrameCount;
 use bevy::pbr::{NotShadowCaster, NotShadowReceiver};
 use bevy::prelude::*;
@@ -66,8 +71,12 @@ fn setup_preview_material(
 
 fn ui_build_panel(
     mut egui_ctx: bevy_egui::EguiContexts,
-    tower_db: Res<TowerDB>,
+    (tower_db, locale): (Res<TowerDB>, Res<Locale>),
+    (gold, lives): (Res<Gold>, Res<Lives>),
+    (wave, schedule, catalog): (Res<WaveState>, Res<WaveSchedule>, Res<TargetCatalog>),
+    sponsorships: Res<Sponsorships>,
     mut select_event_writer: EventWriter<SelectTowerToBuildEvent>,
+    mut actions: GameActions,
 ) {
     let ctx = egui_ctx.ctx_mut().unwrap();
 
@@ -82,16 +91,48 @@ fn ui_build_panel(
                 .stroke(ui.visuals().widgets.noninteractive.bg_stroke);
 
             egui::Frame::show(frame, ui, |ui| {
-                ui.heading("Towers: ");
+                ui.heading(locale.t("hud.towers"));
+                ui.label(locale.fmt("hud.gold", &[("gold", &gold.0)]));
+                ui.label(locale.fmt("hud.lives", &[("lives", &lives.0)]));
+                ui.label(locale.fmt("hud.wave", &[("wave", &wave.index)]));
+                // Кто придёт в следующей волне; описание врага — во всплывающей подсказке.
+                let next = schedule.wave(wave.index + 1);
+                let kind = next.enemy.as_deref().and_then(|e| catalog.find(e));
+                let enemy = kind.map_or(locale.t("hud.random_enemy"), |k| catalog.display_name(k, &locale));
+                let label = ui.label(locale.fmt("hud.next_wave", &[("count", &next.count), ("enemy", &enemy)]));
+                if let Some(description) = kind.and_then(|k| catalog.description(k, &locale)) {
+                    label.on_hover_text(description);
+                }
+                if ui.button(locale.t("hud.start_wave")).clicked()
+                    && let Err(e) = actions.apply(&GameAction::StartWave { wave: None })
+                {
+                    warn!("Cannot start wave: {}", e);
+                }
                 ui.separator();
-                tower_db.defs.iter().for_each(|(name, def)| {
-                    ui.label(format!("Name: {} ", name));
-                    ui.label(format!("damage: {} ", def.damage));
-                    if ui.button("Build!").clicked() {
-                        select_event_writer.write(SelectTowerToBuildEvent(def.clone()));
+                tower_db.defs.iter().for_each(|(_, def)| {
+                    let name = ui.strong(def.display_name(&locale));
+                    if let Some(description) = locale.text(&def.description) {
+                        name.on_hover_text(description);
+                    }
+                    ui.label(locale.fmt("build.damage", &[("damage", &def.damage)]));
+                    if ui.button(locale.t("build.build")).clicked() {
+                        select_event_writer.write(SelectTowerToBuildEvent(def.clone(), None));
                     };
                     ui.separator();
                 });
+                if !sponsorships.pending.is_empty() {
+                    ui.label(locale.t("build.sponsored"));
+                    for s in &sponsorships.pending {
+                        let Some(def) = tower_db.defs.get(&s.tower) else { continue; };
+                        ui.horizontal(|ui| {
+                            let tower = def.display_name(&locale);
+                            ui.label(locale.fmt("build.sponsored_by", &[("tower", &tower), ("viewer", &s.viewer)]));
+                            if ui.button(locale.t("build.place")).clicked() {
+                                select_event_writer.write(SelectTowerToBuildEvent(def.clone(), Some(s.viewer.clone())));
+                            }
+                        });
+                    }
+                }
             });
         });
 }
@@ -99,13 +140,15 @@ fn ui_build_panel(
 #[derive(Resource, Default)]
 struct SelectedToBuildTower {
     def: Option<TowerDef>,
+    /// Зритель-спонсор, если ставим оплаченную башню.
+    owner: Option<String>,
     armed_frame: u32,
     awaiting_click: bool,
     tinted: bool
 }
 
 #[derive(Event, Clone)]
-struct SelectTowerToBuildEvent(pub TowerDef);
+struct SelectTowerToBuildEvent(pub TowerDef, pub Option<String>);
 
 fn on_select_tower(
     mut evr: EventReader<SelectTowerToBuildEvent>,
@@ -114,8 +157,9 @@ fn on_select_tower(
     mut commands: Commands,
     mut preview_ent: ResMut<TowerPreviewEntity>,
 ) {
-    for SelectTowerToBuildEvent(def) in evr.read().cloned() {
+    for SelectTowerToBuildEvent(def, owner) in evr.read().cloned() {
         selected.def = Some(def.clone());
+        selected.owner = owner;
         selected.armed_frame = frames.0;
         selected.awaiting_click = true;
 
@@ -168,7 +212,7 @@ fn update_preview_position(
 }
 
 fn place_selected_tower_on_click(
-    mut commands: Commands,
+    mut actions: GameActions,
     mut selected: ResMut<SelectedToBuildTower>,
     frames: Res<FrameCount>,
     mouse: Res<ButtonInput<MouseButton>>,
@@ -214,7 +258,12 @@ fn place_selected_tower_on_click(
     }
 
     if let Some(pos) = hit_world {
-        spawn_tower_of(&mut commands, def, pos);
+        let id = def.id.clone();
+        let owner = selected.owner.take();
+        let action = GameAction::PlaceTower { tower: id, pos: pos.to_array() };
+        if let Err(e) = actions.apply_as(&action, owner.as_deref()) {
+            warn!("Cannot place tower: {}", e);
+        }
         selected.def = None;
         selected.awaiting_click = false;
         selected.tinted = false;
@@ -0,0 +1,316 @@
+use crate::stream_event::{ChannelSource, StreamEvent, StreamEventSource, StreamSources, ViewerRoles};
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::time::Duration;
+
+/// Чтение чата Twitch по IRC как [`StreamEventSource`](crate::stream_event::StreamEventSource).
+///
+/// Включается переменной окружения `TWITCH_CHANNEL`. Без `TWITCH_OAUTH` подключается
+/// анонимно: только чтение, ответы в чат не отправляются.
+pub struct TwitchIrcPlugin;
+
+impl Plugin for TwitchIrcPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TwitchIrcConfig>()
+            .add_systems(Startup, start_twitch_irc);
+    }
+}
+
+const TWITCH_IRC_ADDR: &str = "irc.chat.twitch.tv:6667";
+const ANONYMOUS_NICK: &str = "justinfan31337";
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+const WRITER_TICK: Duration = Duration::from_millis(200);
+
+#[derive(Resource, Clone)]
+pub struct TwitchIrcConfig {
+    pub channel: Option<String>,
+    pub nick: String,
+    pub oauth: Option<String>,
+}
+
+impl Default for TwitchIrcConfig {
+    fn default() -> Self {
+        Self {
+            channel: std::env::var("TWITCH_CHANNEL").ok().filter(|c| !c.is_empty()),
+            nick: std::env::var("TWITCH_NICK").unwrap_or_else(|_| ANONYMOUS_NICK.to_string()),
+            oauth: std::env::var("TWITCH_OAUTH").ok().filter(|t| !t.is_empty()),
+        }
+    }
+}
+
+/// Twitch-чат как источник: события читаются в фоне, ответы уходят через `outgoing`.
+struct TwitchSource {
+    events: ChannelSource,
+    outgoing: Sender<String>,
+    can_reply: bool,
+}
+
+impl StreamEventSource for TwitchSource {
+    fn name(&self) -> &str {
+        self.events.name()
+    }
+
+    fn poll(&mut self, time: &Time, out: &mut Vec<StreamEvent>) {
+        self.events.poll(time, out);
+    }
+
+    fn reply(&mut self, text: &str) {
+        if self.can_reply {
+            let _ = self.outgoing.send(text.replace(['\r', '\n'], " "));
+        }
+    }
+}
+
+fn start_twitch_irc(config: Res<TwitchIrcConfig>, mut sources: ResMut<StreamSources>) {
+    let Some(channel) = config.channel.clone() else {
+        info!("Twitch IRC disabled: TWITCH_CHANNEL is not set");
+        return;
+    };
+    let channel = channel.trim_start_matches('#').to_lowercase();
+    let (tx, events) = ChannelSource::new(format!("twitch #{}", channel));
+    let (outgoing, outgoing_rx) = mpsc::channel();
+    sources.add(TwitchSource { events, outgoing, can_reply: config.oauth.is_some() });
+
+    let config = config.clone();
+    std::thread::spawn(move || loop {
+        match run_irc_session(&config, &channel, &tx, &outgoing_rx) {
+            Ok(()) => info!("Twitch IRC: connection closed"),
+            Err(e) => warn!("Twitch IRC: {}", e),
+        }
+        std::thread::sleep(RECONNECT_DELAY);
+    });
+}
+
+/// Одна сессия: чтение идёт в отдельном потоке, а этот поток пишет в сокет
+/// сообщения чата из `outgoing` и PONG-и от читателя.
+fn run_irc_session(
+    config: &TwitchIrcConfig,
+    channel: &str,
+    tx: &Sender<StreamEvent>,
+    outgoing: &Receiver<String>,
+) -> std::io::Result<()> {
+    let stream = TcpStream::connect(TWITCH_IRC_ADDR)?;
+    let mut writer = stream.try_clone()?;
+
+    if let Some(oauth) = &config.oauth {
+        let token = oauth.trim_start_matches("oauth:");
+        write!(writer, "PASS oauth:{}\r\n", token)?;
+    }
+    write!(writer, "NICK {}\r\n", config.nick)?;
+    write!(writer, "CAP REQ :twitch.tv/tags twitch.tv/commands\r\n")?;
+    write!(writer, "JOIN #{}\r\n", channel)?;
+    info!("Twitch IRC: joined #{}", channel);
+
+    let (raw_tx, raw_rx) = mpsc::channel::<String>();
+    let events_tx = tx.clone();
+    let reader = std::thread::spawn(move || -> std::io::Result<()> {
+        for line in BufReader::new(stream).lines() {
+            let line = line?;
+            let Some(msg) = IrcMessage::parse(&line) else { continue; };
+            if msg.command == "PING" {
+                let _ = raw_tx.send(format!("PONG :{}", msg.trailing.unwrap_or_default()));
+                continue;
+            }
+            if let Some(ev) = msg.to_stream_event()
+                && events_tx.send(ev).is_err()
+            {
+                break;
+            }
+        }
+        Ok(())
+    });
+
+    while !reader.is_finished() {
+        for raw in raw_rx.try_iter() {
+            write!(writer, "{}\r\n", raw)?;
+        }
+        match outgoing.recv_timeout(WRITER_TICK) {
+            Ok(text) => write!(writer, "PRIVMSG #{} :{}\r\n", channel, text)?,
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    let _ = writer.shutdown(Shutdown::Both);
+    reader.join().unwrap_or(Ok(()))
+}
+
+/// Разобранная строка IRC с тегами Twitch (IRCv3).
+#[derive(Debug, Default)]
+pub struct IrcMessage<'a> {
+    pub tags: HashMap<&'a str, String>,
+    pub nick: Option<&'a str>,
+    pub command: &'a str,
+    pub params: Vec<&'a str>,
+    pub trailing: Option<&'a str>,
+}
+
+impl<'a> IrcMessage<'a> {
+    pub fn parse(line: &'a str) -> Option<Self> {
+        let mut msg = IrcMessage::default();
+        let mut rest = line.trim_end_matches(['\r', '\n']);
+
+        if let Some(tagged) = rest.strip_prefix('@') {
+            let (tags, tail) = tagged.split_once(' ')?;
+            for tag in tags.split(';') {
+                let (key, value) = tag.split_once('=').unwrap_or((tag, ""));
+                msg.tags.insert(key, unescape_tag(value));
+            }
+            rest = tail;
+        }
+        if let Some(prefixed) = rest.strip_prefix(':') {
+            let (prefix, tail) = prefixed.split_once(' ')?;
+            msg.nick = prefix.split('!').next();
+            rest = tail;
+        }
+        let (head, trailing) = match rest.split_once(" :") {
+            Some((head, trailing)) => (head, Some(trailing)),
+            None => (rest, None),
+        };
+        let mut words = head.split_whitespace();
+        msg.command = words.next()?;
+        msg.params = words.collect();
+        msg.trailing = trailing;
+        Some(msg)
+    }
+
+    fn user(&self) -> String {
+        self.tags
+            .get("display-name")
+            .filter(|n| !n.is_empty())
+            .cloned()
+            .or_else(|| self.tags.get("login").cloned())
+            .or_else(|| self.nick.map(str::to_string))
+            .unwrap_or_default()
+    }
+
+    pub fn roles(&self) -> ViewerRoles {
+        let badges = self.tags.get("badges").map(String::as_str).unwrap_or_default();
+        let has_badge = |name: &str| badges.split(',').any(|b| b.split('/').next() == Some(name));
+        let flag = |name: &str| self.tags.get(name).is_some_and(|v| v == "1");
+        ViewerRoles {
+            broadcaster: has_badge("broadcaster"),
+            moderator: flag("mod") || has_badge("moderator"),
+            vip: flag("vip") || has_badge("vip"),
+            subscriber: flag("subscriber") || has_badge("subscriber") || has_badge("founder"),
+        }
+    }
+
+    pub fn to_stream_event(&self) -> Option<StreamEvent> {
+        let user = self.user();
+        match self.command {
+            "PRIVMSG" => {
+                let text = self.trailing.unwrap_or_default().to_string();
+                if let Some(bits) = self.tags.get("bits").and_then(|b| b.parse().ok()) {
+                    return Some(StreamEvent::Bits { user, amount: bits, message: strip_cheermotes(&text) });
+                }
+                if let Some(reward) = self.tags.get("custom-reward-id") {
+                    return Some(StreamEvent::Redemption { user, reward: reward.clone(), input: Some(text) });
+                }
+                Some(StreamEvent::Chat { user, text, roles: self.roles() })
+            }
+            "USERNOTICE" => match self.tags.get("msg-id").map(String::as_str)? {
+                "sub" | "resub" | "subgift" => {
+                    let months = self
+                        .tags
+                        .get("msg-param-cumulative-months")
+                        .and_then(|m| m.parse().ok())
+                        .unwrap_or(1);
+                    Some(StreamEvent::Sub { user, months })
+                }
+                "raid" => {
+                    let viewers = self
+                        .tags
+                        .get("msg-param-viewerCount")
+                        .and_then(|v| v.parse().ok())
+                        .unwrap_or(0);
+                    Some(StreamEvent::Raid { user, viewers })
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+fn unescape_tag(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some(':') => out.push(';'),
+            Some('s') => out.push(' '),
+            Some('r') => out.push('\r'),
+            Some('n') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+/// Убирает из сообщения с битами чирмоуты (`Cheer100`, `Kappa50`…), чтобы команда
+/// вроде `!sponsor` оказалась первым словом.
+pub fn strip_cheermotes(text: &str) -> Option<String> {
+    let is_cheermote = |w: &str| {
+        let digits = w.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+        digits.len() < w.len() && !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+    };
+    let words: Vec<_> = text.split_whitespace().filter(|w| !is_cheermote(w)).collect();
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tags_prefix_and_trailing() {
+        let line = "@badges=moderator/1,subscriber/12;display-name=Viewer\\sOne;mod=1 \
+                    :viewer1!viewer1@viewer1.tmi.twitch.tv PRIVMSG #channel :!spawn Target_2\r\n";
+        let msg = IrcMessage::parse(line).unwrap();
+        assert_eq!(msg.tags["display-name"], "Viewer One");
+        assert_eq!(msg.nick, Some("viewer1"));
+        assert_eq!(msg.command, "PRIVMSG");
+        assert_eq!(msg.params, ["#channel"]);
+        assert_eq!(msg.trailing, Some("!spawn Target_2"));
+    }
+
+    #[test]
+    fn unescapes_tag_values() {
+        assert_eq!(unescape_tag("a\\:b\\sc\\\\d"), "a;b c\\d");
+        assert_eq!(unescape_tag("trailing\\"), "trailing");
+    }
+
+    #[test]
+    fn roles_come_from_badges_and_flags() {
+        let msg = IrcMessage::parse("@badges=broadcaster/1,founder/0;vip=1 :me PRIVMSG #me :hi").unwrap();
+        assert_eq!(msg.roles(), ViewerRoles { broadcaster: true, moderator: false, vip: true, subscriber: true });
+
+        let msg = IrcMessage::parse("@badges=;mod=0;subscriber=0 :me PRIVMSG #me :hi").unwrap();
+        assert_eq!(msg.roles(), ViewerRoles::default());
+    }
+
+    #[test]
+    fn bits_message_becomes_bits_event() {
+        let msg = IrcMessage::parse("@bits=500;display-name=Fan :fan PRIVMSG #channel :Cheer500 !sponsor sniper").unwrap();
+        assert_eq!(
+            msg.to_stream_event(),
+            Some(StreamEvent::Bits { user: "Fan".to_string(), amount: 500, message: Some("!sponsor sniper".to_string()) })
+        );
+    }
+
+    #[test]
+    fn strips_cheermotes() {
+        assert_eq!(strip_cheermotes("Cheer100 !sponsor Kappa50 sniper"), Some("!sponsor sniper".to_string()));
+        assert_eq!(strip_cheermotes("Cheer100 Cheer1"), None);
+        // Слова без числа в конце или из одних цифр — не чирмоуты.
+        assert_eq!(strip_cheermotes("Cheer 100 hello2you"), Some("Cheer 100 hello2you".to_string()));
+    }
+}
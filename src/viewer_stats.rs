@@ -0,0 +1,315 @@
+use crate::cli::arg_value;
+use crate::localization::Locale;
+use crate::moderation::{CommandRejected, Moderation};
+use crate::replay::ReplayPlayback;
+use crate::stream_event::{ChatReply, Permission, StreamEvent};
+use crate::target::{SpawnTargetEvent, TargetDamaged, TargetKilled, TargetLeaked};
+use crate::tower::TowerOwner;
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy_inspector_egui::{bevy_egui, egui};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Статистика зрителей между сессиями: сколько врагов заспавнили, сколько дошло
+/// до конца, сколько урона впитали их враги, сколько раз голосовали.
+pub struct ViewerStatsPlugin;
+
+/// `L` показывает таблицу лидеров.
+pub struct LeaderboardPlugin;
+
+impl Plugin for ViewerStatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ViewerStatsPath>()
+            .add_systems(Startup, load_viewer_stats)
+            .add_systems(
+                Update,
+                (track_spawns, track_leaks, track_damage, track_tower_kills, handle_chat_commands),
+            )
+            // Реплей повторяет уже засчитанное — в файл его не пишем.
+            .add_systems(
+                PostUpdate,
+                (autosave_viewer_stats, save_viewer_stats_on_exit).run_if(not(resource_exists::<ReplayPlayback>)),
+            );
+    }
+}
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LeaderboardVisible>()
+            .add_systems(Update, toggle_leaderboard)
+            .add_systems(
+                bevy_egui::EguiPrimaryContextPass,
+                ui_leaderboard
+                    .run_if(resource_exists::<crate::egui_setup::EguiConfigured>)
+                    .run_if(|visible: Res<LeaderboardVisible>| visible.0),
+            );
+    }
+}
+
+const AUTOSAVE_INTERVAL: f32 = 10.0;
+const LEADERBOARD_SIZE: usize = 10;
+const STATS_COMMAND: &str = "!stats";
+const VOTE_COMMAND: &str = "!vote";
+
+#[derive(Resource, Clone)]
+pub struct ViewerStatsPath(pub PathBuf);
+
+impl Default for ViewerStatsPath {
+    fn default() -> Self {
+        Self(arg_value("--viewer-stats").unwrap_or_else(|| "viewer_stats.json".to_string()).into())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewerStats {
+    /// Имя в том виде, в каком зритель пишет в чат.
+    pub name: String,
+    pub enemies_spawned: u32,
+    pub enemies_leaked: u32,
+    pub damage_absorbed: f32,
+    pub votes_cast: u32,
+    /// Убийства башен, которые спонсировал зритель.
+    pub tower_kills: u32,
+}
+
+impl ViewerStats {
+    pub fn score(&self) -> f32 {
+        self.enemies_leaked as f32 * 10.0
+            + self.enemies_spawned as f32 * 2.0
+            + self.damage_absorbed / 100.0
+            + self.votes_cast as f32
+            + self.tower_kills as f32 * 3.0
+    }
+}
+
+#[derive(Resource, Debug, Default, Serialize, Deserialize)]
+pub struct ViewerStatsDb {
+    /// Ключ — имя в нижнем регистре.
+    pub viewers: BTreeMap<String, ViewerStats>,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl ViewerStatsDb {
+    pub fn entry(&mut self, name: &str) -> &mut ViewerStats {
+        self.dirty = true;
+        let stats = self.viewers.entry(name.to_lowercase()).or_default();
+        if stats.name.is_empty() {
+            stats.name = name.to_string();
+        }
+        stats
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ViewerStats> {
+        self.viewers.get(&name.to_lowercase())
+    }
+
+    pub fn top(&self, n: usize) -> Vec<&ViewerStats> {
+        let mut all: Vec<_> = self.viewers.values().collect();
+        all.sort_by(|a, b| b.score().total_cmp(&a.score()));
+        all.truncate(n);
+        all
+    }
+
+    fn rank_of(&self, name: &str) -> Option<usize> {
+        let key = name.to_lowercase();
+        let mut all: Vec<_> = self.viewers.iter().collect();
+        all.sort_by(|a, b| b.1.score().total_cmp(&a.1.score()));
+        all.iter().position(|(k, _)| **k == key).map(|i| i + 1)
+    }
+}
+
+#[derive(Resource, Default)]
+struct LeaderboardVisible(bool);
+
+fn load_viewer_stats(mut commands: Commands, path: Res<ViewerStatsPath>) {
+    let db = match std::fs::read_to_string(&path.0) {
+        Ok(text) => match serde_json::from_str::<ViewerStatsDb>(&text) {
+            Ok(db) => {
+                info!("Loaded stats of {} viewers from {}", db.viewers.len(), path.0.display());
+                db
+            }
+            Err(e) => {
+                error!("Viewer stats: cannot parse {}: {}", path.0.display(), e);
+                ViewerStatsDb::default()
+            }
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => ViewerStatsDb::default(),
+        Err(e) => {
+            error!("Viewer stats: cannot read {}: {}", path.0.display(), e);
+            ViewerStatsDb::default()
+        }
+    };
+    commands.insert_resource(db);
+}
+
+/// Пишет во временный файл и подменяет им старый: оборванная запись не портит статистику.
+fn save_viewer_stats(db: &mut ViewerStatsDb, path: &ViewerStatsPath) {
+    let tmp = path.0.with_extension("tmp");
+    let result = serde_json::to_string_pretty(db)
+        .map_err(std::io::Error::other)
+        .and_then(|json| std::fs::write(&tmp, json))
+        .and_then(|()| std::fs::rename(&tmp, &path.0));
+    match result {
+        Ok(()) => db.dirty = false,
+        Err(e) => error!("Viewer stats: cannot write {}: {}", path.0.display(), e),
+    }
+}
+
+fn autosave_viewer_stats(
+    db: Option<ResMut<ViewerStatsDb>>,
+    path: Res<ViewerStatsPath>,
+    time: Res<Time>,
+    mut since_save: Local<f32>,
+) {
+    let Some(mut db) = db else { return; };
+    *since_save += time.delta_secs();
+    if db.dirty && *since_save >= AUTOSAVE_INTERVAL {
+        *since_save = 0.0;
+        save_viewer_stats(&mut db, &path);
+    }
+}
+
+fn save_viewer_stats_on_exit(
+    mut exit: EventReader<AppExit>,
+    db: Option<ResMut<ViewerStatsDb>>,
+    path: Res<ViewerStatsPath>,
+) {
+    if exit.read().next().is_none() {
+        return;
+    }
+    if let Some(mut db) = db
+        && db.dirty
+    {
+        save_viewer_stats(&mut db, &path);
+    }
+}
+
+fn track_spawns(mut ev: EventReader<SpawnTargetEvent>, db: Option<ResMut<ViewerStatsDb>>) {
+    let Some(mut db) = db else { return; };
+    for viewer in ev.read().filter_map(|e| e.viewer.as_deref()) {
+        db.entry(viewer).enemies_spawned += 1;
+    }
+}
+
+fn track_leaks(mut ev: EventReader<TargetLeaked>, db: Option<ResMut<ViewerStatsDb>>) {
+    let Some(mut db) = db else { return; };
+    for viewer in ev.read().filter_map(|e| e.spawned_by.as_deref()) {
+        db.entry(viewer).enemies_leaked += 1;
+    }
+}
+
+fn track_damage(mut ev: EventReader<TargetDamaged>, db: Option<ResMut<ViewerStatsDb>>) {
+    let Some(mut db) = db else { return; };
+    for e in ev.read() {
+        if let Some(viewer) = e.spawned_by.as_deref() {
+            db.entry(viewer).damage_absorbed += e.amount;
+        }
+    }
+}
+
+fn track_tower_kills(
+    mut ev: EventReader<TargetKilled>,
+    owners: Query<&TowerOwner>,
+    db: Option<ResMut<ViewerStatsDb>>,
+) {
+    let Some(mut db) = db else { return; };
+    for owner in ev.read().filter_map(|e| owners.get(e.tower?).ok()) {
+        db.entry(&owner.0).tower_kills += 1;
+    }
+}
+
+fn handle_chat_commands(
+    mut ev: EventReader<StreamEvent>,
+    db: Option<ResMut<ViewerStatsDb>>,
+    mut moderation: ResMut<Moderation>,
+    time: Res<Time<Real>>,
+    mut replies: EventWriter<ChatReply>,
+    mut rejected: EventWriter<CommandRejected>,
+) {
+    let Some(mut db) = db else { return; };
+    for e in ev.read() {
+        let StreamEvent::Chat { user, text, .. } = e else { continue; };
+        if !moderation.allows(user) {
+            continue;
+        }
+        let Some(command) = text.split_whitespace().next() else { continue; };
+
+        if command.eq_ignore_ascii_case(VOTE_COMMAND) {
+            // Голос считается только пропущенный модерацией — спам `!vote` упирается в лимиты.
+            match moderation.check(e, Permission::Everyone, time.elapsed_secs_f64()) {
+                Ok(()) => db.entry(user).votes_cast += 1,
+                Err(reason) => {
+                    rejected.write(CommandRejected { user: user.clone(), kind: e.kind(), text: Some(text.clone()), reason });
+                }
+            }
+        } else if command.eq_ignore_ascii_case(STATS_COMMAND) {
+            let reply = match (db.get(user), db.rank_of(user)) {
+                (Some(s), Some(rank)) => format!(
+                    "@{} #{}: spawned {}, leaked {}, absorbed {:.0} dmg, votes {}, tower kills {}",
+                    user, rank, s.enemies_spawned, s.enemies_leaked, s.damage_absorbed, s.votes_cast, s.tower_kills
+                ),
+                _ => format!("@{} no stats yet", user),
+            };
+            replies.write(ChatReply(reply));
+        }
+    }
+}
+
+fn toggle_leaderboard(keyboard: Res<ButtonInput<KeyCode>>, mut visible: ResMut<LeaderboardVisible>) {
+    if keyboard.just_pressed(KeyCode::KeyL) {
+        visible.0 = !visible.0;
+    }
+}
+
+fn ui_leaderboard(mut egui_ctx: bevy_egui::EguiContexts, db: Option<Res<ViewerStatsDb>>, locale: Res<Locale>) {
+    let Some(db) = db else { return; };
+    let Ok(ctx) = egui_ctx.ctx_mut() else { return; };
+
+    egui::Area::new(egui::Id::new("leaderboard_area"))
+        .anchor(egui::Align2::LEFT_BOTTOM, [12.0, -12.0])
+        .show(ctx, |ui| {
+            let frame = egui::Frame::window(&ctx.style())
+                .fill(ui.visuals().panel_fill)
+                .corner_radius(egui::CornerRadius::same(6))
+                .inner_margin(egui::Margin::symmetric(10, 8))
+                .stroke(ui.visuals().widgets.noninteractive.bg_stroke);
+
+            egui::Frame::show(frame, ui, |ui| {
+                ui.heading(locale.t("leaderboard.title"));
+                ui.separator();
+                let top = db.top(LEADERBOARD_SIZE);
+                if top.is_empty() {
+                    ui.label(locale.t("leaderboard.empty"));
+                    return;
+                }
+                egui::Grid::new("leaderboard_grid").striped(true).show(ui, |ui| {
+                    ui.label("#");
+                    for key in [
+                        "leaderboard.viewer",
+                        "leaderboard.spawned",
+                        "leaderboard.leaked",
+                        "leaderboard.absorbed",
+                        "leaderboard.votes",
+                        "leaderboard.tower_kills",
+                    ] {
+                        ui.label(locale.t(key));
+                    }
+                    ui.end_row();
+                    for (i, s) in top.iter().enumerate() {
+                        ui.label(format!("{}", i + 1));
+                        ui.label(&s.name);
+                        ui.label(format!("{}", s.enemies_spawned));
+                        ui.label(format!("{}", s.enemies_leaked));
+                        ui.label(format!("{:.0}", s.damage_absorbed));
+                        ui.label(format!("{}", s.votes_cast));
+                        ui.label(format!("{}", s.tower_kills));
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+}
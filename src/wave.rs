@@ -0,0 +1,265 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::AppState;
+use crate::StartupStage::Processing;
+use crate::data_format::DataAssetPlugin;
+use crate::data_load::{DataErrors, assets_dir};
+use crate::level::ActiveLevel;
+use crate::loading::LoadingTracker;
+use crate::mods::ModPacks;
+use crate::target::{SpawnTargetEvent, TargetCatalog, WaveTarget};
+
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(DataAssetPlugin::<WaveSchedule>::default())
+            .init_resource::<WaveState>()
+            .init_resource::<WaveSchedule>()
+            .add_event::<StartWaveEvent>()
+            .add_event::<WaveEnded>()
+            .add_systems(
+                Update,
+                (
+                    load_wave_schedule.run_if(resource_exists_and_changed::<ActiveLevel>),
+                    apply_wave_schedule,
+                )
+                    .in_set(Processing),
+            )
+            .add_systems(
+                FixedUpdate,
+                (start_wave, spawn_wave_targets, detect_wave_end)
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+pub const WAVES_JSON: &str = "data/waves.json";
+const WAVE_SPAWN_INTERVAL: f32 = 0.6;
+const WAVE_BASE_SIZE: u32 = 5;
+const WAVE_SIZE_STEP: u32 = 2;
+
+#[derive(Resource)]
+pub struct WaveState {
+    pub index: u32,
+    pub pending: u32,
+    /// Последняя волна, все цели которой уже убиты или прошли.
+    pub ended: u32,
+    spawn_timer: Timer,
+}
+
+impl Default for WaveState {
+    fn default() -> Self {
+        Self {
+            index: 0,
+            pending: 0,
+            ended: 0,
+            spawn_timer: Timer::from_seconds(WAVE_SPAWN_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+impl WaveState {
+    /// Состояние из сохранения; `interval` — из описания волны `index`, его задаёт `start_wave`.
+    pub fn restored(index: u32, pending: u32, ended: u32, interval: f32, spawn_elapsed: f32) -> Self {
+        let mut spawn_timer = Timer::from_seconds(interval, TimerMode::Repeating);
+        spawn_timer.set_elapsed(std::time::Duration::from_secs_f32(spawn_elapsed.max(0.0)));
+        Self { index, pending, ended, spawn_timer }
+    }
+
+    /// Сколько секунд прошло с появления последней цели волны.
+    pub fn spawn_elapsed(&self) -> f32 {
+        self.spawn_timer.elapsed_secs()
+    }
+}
+
+/// Одна волна из `data/waves.json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WaveDef {
+    pub count: u32,
+    /// Имя врага из каталога целей; без него — случайный вид.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enemy: Option<String>,
+    /// Сколько последних целей волны — боссы.
+    #[serde(default)]
+    pub bosses: u32,
+    /// Секунды между появлениями целей.
+    #[serde(default = "default_interval")]
+    pub interval: f32,
+}
+
+fn default_interval() -> f32 {
+    WAVE_SPAWN_INTERVAL
+}
+
+/// Расписание волн. Волны после последней описанной повторяют её и растут на
+/// `WAVE_SIZE_STEP` целей каждая; пустое расписание — встроенная формула [`wave_size`].
+#[derive(Resource, Asset, TypePath, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WaveSchedule {
+    pub waves: Vec<WaveDef>,
+}
+
+impl WaveSchedule {
+    /// Волна с номером `index`, считая с 1.
+    pub fn wave(&self, index: u32) -> WaveDef {
+        let described = self.waves.len() as u32;
+        match self.waves.last() {
+            Some(_) if index >= 1 && index <= described => self.waves[index as usize - 1].clone(),
+            Some(last) => WaveDef {
+                count: last.count + WAVE_SIZE_STEP * index.saturating_sub(described),
+                ..last.clone()
+            },
+            None => WaveDef { count: wave_size(index), enemy: None, bosses: 0, interval: WAVE_SPAWN_INTERVAL },
+        }
+    }
+
+    /// Ошибки с файлом, номером волны и полем; пустой список — расписание годно.
+    pub fn validate(&self, file: &str, catalog: &TargetCatalog) -> Vec<String> {
+        let mut errors = Vec::new();
+        for (i, w) in self.waves.iter().enumerate() {
+            let mut error = |msg: String| errors.push(format!("{}: wave {}: {}", file, i + 1, msg));
+            if w.count == 0 {
+                error("field `count` must be positive".to_string());
+            }
+            if w.bosses > w.count {
+                error(format!("field `bosses` ({}) is greater than `count` ({})", w.bosses, w.count));
+            }
+            if w.interval.is_nan() || w.interval <= 0.0 {
+                error(format!("field `interval` must be positive, got {}", w.interval));
+            }
+            if let Some(enemy) = &w.enemy
+                && catalog.find(enemy).is_none()
+            {
+                error(format!("field `enemy`: unknown enemy '{}'", enemy));
+            }
+        }
+        errors
+    }
+}
+
+/// Файл волн задаёт уровень; путь берётся у последнего пака, где этот файл есть:
+/// расписание не сливается, а заменяется.
+#[derive(Resource)]
+struct WaveScheduleHandle {
+    /// Файл из уровня, например `data/waves.json`.
+    file: String,
+    path: String,
+    handle: Handle<WaveSchedule>,
+}
+
+#[derive(Event, Debug, Clone, Copy, Default)]
+pub struct StartWaveEvent;
+
+/// Все запущенные волны до `wave` включительно закончились.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct WaveEnded {
+    pub wave: u32,
+}
+
+pub fn wave_size(index: u32) -> u32 {
+    WAVE_BASE_SIZE + WAVE_SIZE_STEP * index.saturating_sub(1)
+}
+
+fn load_wave_schedule(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    packs: Res<ModPacks>,
+    level: Res<ActiveLevel>,
+    current: Option<Res<WaveScheduleHandle>>,
+    mut tracker: ResMut<LoadingTracker>,
+) {
+    let file = &level.def.waves;
+    if current.is_some_and(|c| c.file == *file) {
+        return;
+    }
+    let layers = packs.layers(&assets_dir(), file);
+    let path = layers.last().cloned().unwrap_or_else(|| file.clone());
+    if layers.len() > 2 {
+        warn!("{} is defined in {} packs, using {}", file, layers.len() - 1, path);
+    }
+    let handle: Handle<WaveSchedule> = asset_server.load(path.clone());
+    tracker.track(path.clone(), handle.clone());
+    commands.insert_resource(WaveScheduleHandle { file: file.clone(), path, handle });
+}
+
+fn apply_wave_schedule(
+    mut events: EventReader<AssetEvent<WaveSchedule>>,
+    handle: Option<Res<WaveScheduleHandle>>,
+    schedules: Res<Assets<WaveSchedule>>,
+    catalog: Res<TargetCatalog>,
+    mut schedule: ResMut<WaveSchedule>,
+    mut data_errors: ResMut<DataErrors>,
+) {
+    let Some(handle) = handle else { return; };
+    // Новый хэндл уже может быть загружен, если другой уровень брал тот же файл.
+    let updated = events.read().any(|ev| ev.is_loaded_with_dependencies(&handle.handle) || ev.is_modified(&handle.handle));
+    if !(updated || handle.is_changed() || catalog.is_changed()) {
+        return;
+    }
+    // Врагов из волн проверим, когда загрузится их файл.
+    if catalog.is_empty() {
+        return;
+    }
+    let Some(loaded) = schedules.get(&handle.handle) else { return; };
+    data_errors.0.retain(|e| !e.starts_with(&handle.path));
+    let errors = loaded.validate(&handle.path, &catalog);
+    if errors.is_empty() {
+        info!("Wave schedule applied from {}: {} waves", handle.path, loaded.waves.len());
+        *schedule = loaded.clone();
+        return;
+    }
+    for e in errors {
+        error!("{}", e);
+        data_errors.0.push(e);
+    }
+    warn!("{} has errors, keeping the previous wave schedule", handle.path);
+}
+
+fn start_wave(mut ev: EventReader<StartWaveEvent>, mut wave: ResMut<WaveState>, schedule: Res<WaveSchedule>) {
+    for _ in ev.read() {
+        wave.index += 1;
+        let def = schedule.wave(wave.index);
+        wave.pending += def.count;
+        wave.spawn_timer.set_duration(std::time::Duration::from_secs_f32(def.interval));
+        info!("Wave {} started: {} targets", wave.index, def.count);
+    }
+}
+
+fn spawn_wave_targets(
+    mut wave: ResMut<WaveState>,
+    schedule: Res<WaveSchedule>,
+    catalog: Res<TargetCatalog>,
+    time: Res<Time>,
+    mut spawn_ev: EventWriter<SpawnTargetEvent>,
+) {
+    if wave.pending == 0 {
+        return;
+    }
+    if !wave.spawn_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    // Недоспавненные цели прошлой волны идут по описанию текущей; боссы — в конце.
+    let def = schedule.wave(wave.index);
+    let boss = wave.pending <= def.bosses;
+    let kind = def.enemy.as_deref().and_then(|name| catalog.find(name));
+    wave.pending -= 1;
+    spawn_ev.write(SpawnTargetEvent { kind, boss, wave: Some(wave.index), ..default() });
+}
+
+fn detect_wave_end(
+    mut wave: ResMut<WaveState>,
+    spawn_ev: EventReader<SpawnTargetEvent>,
+    targets: Query<(), With<WaveTarget>>,
+    mut ended_ev: EventWriter<WaveEnded>,
+) {
+    // Цель из только что отправленного события появится лишь на следующем шаге.
+    if wave.ended == wave.index || wave.pending > 0 || !spawn_ev.is_empty() || !targets.is_empty() {
+        return;
+    }
+    wave.ended = wave.index;
+    info!("Wave {} ended", wave.index);
+    ended_ev.write(WaveEnded { wave: wave.index });
+}
@@ -1,41 +1,123 @@
-use bevy::app::{App, Plugin, Startup};
+use bevy::app::{App, Plugin};
 use bevy::asset::Assets;
-use bevy::color::{Color, Srgba};
-use bevy::pbr::{MeshMaterial3d, PointLight, StandardMaterial};
+use bevy::color::Color;
+use bevy::pbr::{MeshMaterial3d, NotShadowCaster, PointLight, StandardMaterial};
 use bevy::prelude::*;
+use crate::StartupStage::Processing;
+use crate::level::{ActiveLevel, LightDef, TerrainDef};
+use crate::loading::LoadingTracker;
 
-#[derive(Reflect, Component, Default)]
-#[reflect(Component)]
-pub struct Game {
-    pub target_spawn_timer: Timer,
-}
-
+/// Сцена уровня из [`ActiveLevel`]: земля, свет, камера и подсветка зон строительства.
+/// Пересобирается при каждой смене или правке уровня.
 pub struct WorldPlugin;
 
 impl Plugin for WorldPlugin {
     fn build(&self,app: &mut App){
         app
-            .register_type::<Game>()
-            .add_systems(Startup, (spawn_light, spawn_scene));
+            .add_systems(Update, (clear_level_scene, spawn_scene, spawn_lights, spawn_build_zones, place_camera)
+                .chain()
+                .in_set(Processing)
+                .run_if(resource_exists_and_changed::<ActiveLevel>));
+    }
+}
 
-        return;
+/// Всё, что построено по уровню и убирается при его смене.
+#[derive(Component)]
+struct LevelScene;
+
+const BUILD_ZONE_COLOR: Color = Color::srgba(0.95, 0.8, 0.35, 0.2);
+
+fn clear_level_scene(mut commands: Commands, q: Query<Entity, With<LevelScene>>) {
+    for e in &q {
+        commands.entity(e).despawn();
+    }
+}
+
+fn spawn_lights(mut commands: Commands, level: Res<ActiveLevel>, ambient: Option<ResMut<AmbientLight>>){
+    for (i, light) in level.def.lights.iter().enumerate() {
+        let name = Name::new(format!("Light {}", i));
+        match *light {
+            LightDef::Point { pos, intensity, range, shadows } => {
+                let mut point = PointLight { shadows_enabled: shadows, ..default() };
+                point.intensity = intensity.unwrap_or(point.intensity);
+                point.range = range.unwrap_or(point.range);
+                commands.spawn((point, Transform::from_translation(Vec3::from_array(pos)), name, LevelScene));
+            }
+            LightDef::Directional { direction, illuminance, shadows } => {
+                let mut sun = DirectionalLight { shadows_enabled: shadows, ..default() };
+                sun.illuminance = illuminance.unwrap_or(sun.illuminance);
+                let transform = Transform::default().looking_to(Vec3::from_array(direction), Vec3::Y);
+                commands.spawn((sun, transform, name, LevelScene));
+            }
+        }
     }
+    if let (Some(def), Some(mut ambient)) = (&level.def.ambient, ambient) {
+        let [r, g, b] = def.color;
+        ambient.color = Color::srgb(r, g, b);
+        ambient.brightness = def.brightness;
+    }
+}
+
+fn spawn_scene(
+    mut commands: Commands,
+    level: Res<ActiveLevel>,
+    (mut meshes, mut materials): (ResMut<Assets<Mesh>>, ResMut<Assets<StandardMaterial>>),
+    (server, mut tracker): (Res<AssetServer>, ResMut<LoadingTracker>),
+){
+    let mut ground = match &level.def.terrain {
+        TerrainDef::Ground { size, color } => {
+            let [r, g, b] = *color;
+            commands.spawn((
+                Mesh3d(meshes.add(Plane3d::default().mesh().size(size[0], size[1]).subdivisions(10))),
+                MeshMaterial3d(materials.add(Color::srgb(r, g, b))),
+            ))
+        }
+        TerrainDef::Scene(path) => {
+            let scene = server.load(GltfAssetLabel::Scene(0).from_asset(path.clone()));
+            tracker.track(path.clone(), scene.clone());
+            commands.spawn(SceneRoot(scene))
+        }
+    };
+    ground.insert((Name::new("Scene"), LevelScene));
 }
 
-fn spawn_light(mut commands: Commands){
-    commands.spawn((PointLight {
-        shadows_enabled: true,
+fn spawn_build_zones(
+    mut commands: Commands,
+    level: Res<ActiveLevel>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+){
+    if level.def.build_zones.is_empty() {
+        return;
+    }
+    let material = materials.add(StandardMaterial {
+        base_color: BUILD_ZONE_COLOR,
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
         ..default()
-    },
-                    Transform::from_xyz(5.0, 5.0, 5.0),
-    )).insert(Name::new("Sun"));
+    });
+    for zone in &level.def.build_zones {
+        let size = Vec2::new(zone.max[0] - zone.min[0], zone.max[1] - zone.min[1]);
+        let center = Vec3::new(zone.min[0] + size.x * 0.5, 0.01, zone.min[1] + size.y * 0.5);
+        commands.spawn((
+            Mesh3d(meshes.add(Plane3d::default().mesh().size(size.x, size.y))),
+            MeshMaterial3d(material.clone()),
+            Transform::from_translation(center),
+            NotShadowCaster,
+            Name::new("Build zone"),
+            LevelScene,
+        ));
+    }
 }
 
-fn spawn_scene(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials: ResMut<Assets<StandardMaterial>>){
-    commands.spawn((
-        Mesh3d(meshes.add(Plane3d::default().mesh().size(50.0, 50.0).subdivisions(10))),
-        MeshMaterial3d(materials.add(Color::from(Srgba::new(0.3, 0.5, 0.3, 1.0)))),
-    ))
-        .insert(Name::new("Scene"))
-        .insert(Game {target_spawn_timer: Timer::from_seconds(2.0, TimerMode::Repeating)});
-}
\ No newline at end of file
+/// Камера ставится только при смене уровня, правка файла её не трогает.
+fn place_camera(level: Res<ActiveLevel>, mut cameras: Query<&mut Transform, With<Camera3d>>, mut placed_for: Local<String>){
+    if *placed_for == level.id {
+        return;
+    }
+    *placed_for = level.id.clone();
+    let Some(camera) = &level.def.camera else { return; };
+    for mut t in &mut cameras {
+        *t = Transform::from_translation(Vec3::from_array(camera.pos)).looking_at(Vec3::from_array(camera.look_at), Vec3::Y);
+    }
+}
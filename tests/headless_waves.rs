@@ -0,0 +1,70 @@
+use bevy::prelude::*;
+use stream_td::headless::SimHarness;
+use stream_td::target::AMBIENT_SPAWN_SECONDS;
+use stream_td::wave::wave_size;
+
+const FPS: u32 = 60;
+/// Цель идёт от x = -2 до утечки на x = 20 со скоростью 0.3 — чуть больше минуты.
+/// Нечётное число: конец прогона не совпадает со срабатыванием фонового спавна.
+const WAVE_SECONDS: u32 = 95;
+
+#[test]
+fn ambient_targets_spawn_without_waves() {
+    let mut sim = SimHarness::new();
+    // Полшага таймера сверху, чтобы не попасть на границу.
+    let seconds = 5.5 * AMBIENT_SPAWN_SECONDS;
+    sim.step((seconds * FPS as f32) as u32);
+
+    assert_eq!(sim.counters().spawned, 5);
+}
+
+#[test]
+fn first_wave_leaks_without_towers() {
+    let mut sim = SimHarness::new();
+    sim.clear_towers();
+    sim.start_wave();
+    sim.step(WAVE_SECONDS * FPS);
+
+    // Вместе с волной шли и фоновые цели; дойти успели те, что вышли в первые секунды.
+    let c = sim.counters();
+    let ambient = (WAVE_SECONDS as f32 / AMBIENT_SPAWN_SECONDS) as u32;
+    assert_eq!(c.spawned, wave_size(1) + ambient);
+    assert_eq!(c.kills, 0);
+    assert!(c.leaks > wave_size(1) && c.leaks < c.spawned, "{:?}", c);
+}
+
+#[test]
+fn starting_towers_kill_first_wave_targets() {
+    let mut sim = SimHarness::new();
+    // Фоновые цели идут у самых башен и перетягивают огонь на себя — здесь только волна.
+    sim.pause_ambient_spawn();
+    sim.start_wave();
+    sim.step(WAVE_SECONDS * FPS);
+
+    let c = sim.counters();
+    assert!(c.kills + c.leaks <= c.spawned, "{:?}", c);
+    assert!(c.kills > 0, "towers killed nothing: {:?}", c);
+}
+
+#[test]
+fn placed_tower_adds_kills() {
+    let mut sim = SimHarness::new();
+    sim.clear_towers();
+    sim.place_tower("sniper", Vec3::new(6.0, 0.0, 0.0));
+    sim.place_tower("basic", Vec3::new(9.0, 0.0, 0.0));
+    sim.start_wave();
+    sim.step(WAVE_SECONDS * FPS);
+
+    assert!(sim.counters().kills > 0, "{:?}", sim.counters());
+}
+
+#[test]
+fn same_seed_gives_same_outcome() {
+    let run = |seed| {
+        let mut sim = SimHarness::with_seed(seed);
+        sim.start_wave();
+        sim.step(WAVE_SECONDS * FPS);
+        sim.counters()
+    };
+    assert_eq!(run(42), run(42));
+}
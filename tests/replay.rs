@@ -0,0 +1,43 @@
+use stream_td::economy::{Gold, Lives};
+use stream_td::game_action::GameAction;
+use stream_td::headless::{SimCounters, SimHarness};
+use stream_td::replay::ReplayConfig;
+use stream_td::stream_event::{StreamEvent, ViewerRoles};
+use stream_td::tower::{Tower, TowerId};
+
+const FPS: u32 = 60;
+
+/// То, что должно совпасть у игры и её реплея.
+fn outcome(sim: &mut SimHarness) -> (SimCounters, u32, u32, Vec<(u32, u32)>) {
+    let counters = sim.counters();
+    let world = sim.app.world_mut();
+    let mut kills: Vec<_> = world.query::<(&TowerId, &Tower)>().iter(world).map(|(id, t)| (id.0, t.kills)).collect();
+    kills.sort();
+    (counters, world.resource::<Gold>().0, world.resource::<Lives>().0, kills)
+}
+
+#[test]
+fn replay_repeats_recorded_match() {
+    let path = std::env::temp_dir().join(format!("stream_td_replay_{}.jsonl", std::process::id()));
+
+    let mut live = SimHarness::with_replay(5, ReplayConfig { play: None, record: Some(path.clone()) });
+    let place = GameAction::PlaceTower { tower: "basic".to_string(), pos: [6.0, 0.0, 0.0] };
+    live.apply(&place).unwrap();
+    live.step(FPS);
+    live.apply(&GameAction::StartWave { wave: None }).unwrap();
+    live.step(10 * FPS);
+    live.send(StreamEvent::Raid { user: "raider".to_string(), viewers: 30 });
+    live.step(5 * FPS);
+    let subscriber = ViewerRoles { subscriber: true, ..ViewerRoles::default() };
+    live.send(StreamEvent::Chat { user: "viewer1".to_string(), text: "!spawn".to_string(), roles: subscriber });
+    live.step(60 * FPS);
+    let recorded = outcome(&mut live);
+    drop(live);
+
+    let mut replayed = SimHarness::with_replay(99, ReplayConfig { play: Some(path.clone()), record: None });
+    replayed.step(76 * FPS);
+    let _ = std::fs::remove_file(&path);
+
+    assert!(recorded.0.spawned > 0, "{:?}", recorded);
+    assert_eq!(outcome(&mut replayed), recorded);
+}
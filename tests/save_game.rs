@@ -0,0 +1,34 @@
+use stream_td::headless::SimHarness;
+
+const FPS: u32 = 60;
+
+#[test]
+fn save_survives_load_into_another_match() {
+    let mut sim = SimHarness::with_seed(7);
+    sim.start_wave();
+    sim.step(20 * FPS);
+    let saved = sim.save_game();
+    assert!(!saved.targets.is_empty(), "nothing to save: {:?}", saved);
+
+    let mut other = SimHarness::with_seed(99);
+    other.load_game(saved.clone());
+    let loaded = other.save_game();
+
+    assert_eq!(loaded.rng, saved.rng);
+    assert_eq!(loaded.tick, saved.tick);
+    assert_eq!(loaded.wave.index, saved.wave.index);
+    assert_eq!(loaded.wave.pending, saved.wave.pending);
+    assert_eq!(loaded.wave.spawn_elapsed, saved.wave.spawn_elapsed);
+    assert_eq!((loaded.gold, loaded.lives), (saved.gold, saved.lives));
+    assert_eq!(loaded.ambient_spawn, saved.ambient_spawn);
+    let towers = |s: &stream_td::save_game::SaveGame| {
+        s.towers.iter().map(|t| (t.id, t.def.clone(), t.level, t.pos)).collect::<Vec<_>>()
+    };
+    assert_eq!(towers(&loaded), towers(&saved));
+    let health = |s: &stream_td::save_game::SaveGame| {
+        let mut h: Vec<_> = s.targets.iter().map(|t| (t.kind.clone(), t.health, t.pos)).collect();
+        h.sort_by(|a, b| a.2[0].total_cmp(&b.2[0]));
+        h
+    };
+    assert_eq!(health(&loaded), health(&saved));
+}
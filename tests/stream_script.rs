@@ -0,0 +1,31 @@
+use bevy::prelude::*;
+use stream_td::headless::SimHarness;
+use stream_td::stream_event::StreamSources;
+use stream_td::stream_script::ScriptedSource;
+use stream_td::target::{Boss, SpawnedBy};
+
+const FPS: u32 = 60;
+
+/// Подписчик пишет `!spawn`, обычный зритель — тоже, потом приходит рейд.
+const SCRIPT: &str = r#"
+{"t": 0.5, "event": {"type": "chat", "user": "viewer1", "text": "!spawn", "roles": {"subscriber": true}}}
+{"t": 1.0, "event": {"type": "chat", "user": "viewer2", "text": "!spawn"}}
+{"t": 1.5, "event": {"type": "raid", "user": "friend", "viewers": 120}}
+"#;
+
+#[test]
+fn scripted_chat_and_raid_spawn_targets() {
+    let mut sim = SimHarness::new();
+    sim.pause_ambient_spawn();
+    let source = ScriptedSource::from_jsonl("inline", SCRIPT).unwrap();
+    sim.app.world_mut().resource_mut::<StreamSources>().add(source);
+    sim.step(3 * FPS);
+
+    let world = sim.app.world_mut();
+    let mut spawned: Vec<_> =
+        world.query::<(&SpawnedBy, Has<Boss>)>().iter(world).map(|(by, boss)| (by.0.clone(), boss)).collect();
+    spawned.sort();
+    // `!spawn` доступен только подписчикам.
+    assert_eq!(spawned, [("friend".to_string(), true), ("viewer1".to_string(), false)]);
+    assert_eq!(sim.counters().spawned, 2);
+}
@@ -0,0 +1,120 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use std::path::PathBuf;
+use stream_td::headless::{SimCounters, SimHarness};
+use stream_td::stream_event::{ChatReply, StreamEvent};
+use stream_td::target::SpawnTargetEvent;
+use stream_td::viewer_stats::{ViewerStats, ViewerStatsDb};
+
+const FPS: u32 = 60;
+
+fn stats_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("stream_td_{}_{}.json", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn spawn_for(sim: &mut SimHarness, viewer: &str) {
+    sim.send(SpawnTargetEvent { kind: None, boss: false, viewer: Some(viewer.to_string()), wave: None });
+}
+
+fn chat(sim: &mut SimHarness, user: &str, text: &str) {
+    sim.send(StreamEvent::Chat { user: user.to_string(), text: text.to_string(), roles: default() });
+}
+
+fn stats(sim: &SimHarness, viewer: &str) -> ViewerStats {
+    sim.app.world().resource::<ViewerStatsDb>().get(viewer).cloned().unwrap_or_default()
+}
+
+fn replies(sim: &SimHarness) -> Vec<String> {
+    let events = sim.app.world().resource::<Events<ChatReply>>();
+    events.get_cursor().read(events).map(|r| r.0.clone()).collect()
+}
+
+#[test]
+fn spawns_leaks_and_damage_go_to_the_viewer() {
+    let path = stats_path("viewer_stats_attribution");
+    let mut sim = SimHarness::with_viewer_stats(1, &path);
+    sim.pause_ambient_spawn();
+
+    // Стартовые башни стреляют по цели зрителя — урон записывается ему.
+    spawn_for(&mut sim, "Shooter");
+    let done = sim.step_until(120 * FPS, |world| {
+        let counters = world.resource::<SimCounters>();
+        counters.kills + counters.leaks > 0
+    });
+    assert!(done, "target neither died nor leaked");
+    let shooter = stats(&sim, "shooter");
+    assert_eq!(shooter.name, "Shooter");
+    assert_eq!(shooter.enemies_spawned, 1);
+    assert!(shooter.damage_absorbed > 0.0, "{:?}", shooter);
+
+    // Без башен цель доходит до конца пути.
+    sim.clear_towers();
+    spawn_for(&mut sim, "Runner");
+    let leaks_before = sim.counters().leaks;
+    let leaked = sim.step_until(120 * FPS, |world| world.resource::<SimCounters>().leaks > leaks_before);
+    assert!(leaked, "target did not reach the end of the path");
+    let runner = stats(&sim, "runner");
+    assert_eq!((runner.enemies_spawned, runner.enemies_leaked), (1, 1));
+    assert_eq!(runner.damage_absorbed, 0.0);
+    assert_eq!(stats(&sim, "shooter").enemies_spawned, 1);
+}
+
+#[test]
+fn stats_command_replies_in_chat() {
+    let path = stats_path("viewer_stats_replies");
+    let mut sim = SimHarness::with_viewer_stats(2, &path);
+    sim.pause_ambient_spawn();
+
+    chat(&mut sim, "Nobody", "!stats");
+    sim.step(1);
+    assert_eq!(replies(&sim), ["@Nobody no stats yet"]);
+
+    spawn_for(&mut sim, "Alice");
+    sim.step(1);
+    chat(&mut sim, "alice", "!STATS please");
+    sim.step(1);
+    assert_eq!(
+        replies(&sim).last().map(String::as_str),
+        Some("@alice #1: spawned 1, leaked 0, absorbed 0 dmg, votes 0, tower kills 0")
+    );
+}
+
+#[test]
+fn votes_over_the_rate_limit_are_not_counted() {
+    let path = stats_path("viewer_stats_votes");
+    let mut sim = SimHarness::with_viewer_stats(3, &path);
+
+    // `data/moderation.json`: не больше трёх команд зрителя за 10 секунд.
+    for _ in 0..5 {
+        chat(&mut sim, "voter", "!vote");
+    }
+    sim.step(1);
+    assert_eq!(stats(&sim, "voter").votes_cast, 3);
+}
+
+#[test]
+fn stats_survive_restart() {
+    let path = stats_path("viewer_stats_restart");
+    let mut sim = SimHarness::with_viewer_stats(4, &path);
+    sim.pause_ambient_spawn();
+    spawn_for(&mut sim, "Alice");
+    chat(&mut sim, "Alice", "!vote");
+    sim.step(1);
+    sim.send(AppExit::Success);
+    sim.step(1);
+    let saved = stats(&sim, "alice");
+    assert!(path.exists(), "{} was not written on exit", path.display());
+
+    let restarted = SimHarness::with_viewer_stats(5, &path);
+    let loaded = stats(&restarted, "alice");
+    assert_eq!(loaded.name, "Alice");
+    assert_eq!(
+        (loaded.enemies_spawned, loaded.enemies_leaked, loaded.votes_cast, loaded.tower_kills),
+        (saved.enemies_spawned, saved.enemies_leaked, saved.votes_cast, saved.tower_kills)
+    );
+    assert_eq!(loaded.damage_absorbed, saved.damage_absorbed);
+    assert_eq!((loaded.enemies_spawned, loaded.votes_cast), (1, 1));
+    let _ = std::fs::remove_file(&path);
+}